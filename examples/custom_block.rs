@@ -0,0 +1,56 @@
+//! A yablocks binary with an extra `counter` block kind.
+//!
+//! Try it with `cargo run --example custom_block -- --configfile <file>`, using a config like:
+//!
+//! ```toml
+//! [blocks.count]
+//! kind = "counter"
+//! interval = 1
+//! template = "count: {{ count }}"
+//! ```
+use std::time::Duration;
+
+use clap::Parser;
+use futures::{stream, StreamExt};
+use yablocks::{BlockStream, BlockStreamConfig, RENDERER};
+
+const DEFAULT_TEMPLATE: &str = "{{count}}";
+
+#[derive(serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct CounterConfig {
+    template: Option<String>,
+    interval: u64,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+struct BlockData {
+    count: u64,
+}
+
+impl BlockStreamConfig for CounterConfig {
+    fn to_stream(self, name: String) -> anyhow::Result<BlockStream> {
+        let template = self.template.as_deref().unwrap_or(DEFAULT_TEMPLATE);
+        RENDERER.add_template(&name, template)?;
+
+        let interval = Duration::from_secs(self.interval);
+        let stream = stream::iter(0..).then(move |count| {
+            let name = name.clone();
+            async move {
+                if count > 0 {
+                    tokio::time::sleep(interval).await;
+                }
+                let result = RENDERER.render(&name, BlockData { count });
+                (name, result)
+            }
+        });
+
+        Ok(Box::pin(stream))
+    }
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    yablocks::register_block::<CounterConfig, BlockData>("counter", DEFAULT_TEMPLATE)?;
+    yablocks::cli::run(yablocks::cli::Args::parse()).await
+}
@@ -3,25 +3,144 @@ mod cpu;
 mod datetime;
 mod inotify;
 mod interval;
+mod marquee;
 mod network;
 mod network_stats;
 mod noop;
+mod plugin;
 mod pulse_volume;
+mod replay;
+mod script;
 mod signal;
 mod stdin;
 mod temperature;
 mod util;
 
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
 use anyhow::Result;
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 
 use crate::config;
+use crate::describe::{schema_for, KindDescription};
+use crate::RENDERER;
 
-type BlockStream = futures::stream::BoxStream<'static, (String, Result<String>)>;
+pub type BlockStream = futures::stream::BoxStream<'static, (String, Result<String>)>;
 
+/// A block's config, which can start the block.
+///
+/// `to_stream` should add the block's template with [`Renderer::add_template`], and render
+/// each update with [`Renderer::render`].
+///
+/// [`Renderer::add_template`]: crate::renderer::Renderer::add_template
+/// [`Renderer::render`]: crate::renderer::Renderer::render
 pub trait BlockStreamConfig {
     fn to_stream(self, name: String) -> Result<BlockStream>;
 }
 
+/// A block kind added with [`register_block`].
+#[derive(Clone, Copy)]
+struct CustomKind {
+    check_config: fn(toml::Value) -> std::result::Result<(), toml::de::Error>,
+    to_stream: fn(toml::Value, String) -> Result<BlockStream>,
+    parse_data: fn(serde_json::Value) -> Result<serde_json::Value>,
+    describe: fn(&'static str) -> KindDescription,
+    default_template: &'static str,
+}
+
+static CUSTOM_KINDS: Lazy<Mutex<BTreeMap<&'static str, CustomKind>>> = Lazy::new(Default::default);
+
+/// Add a block kind, configured by `C`, whose template is rendered with `D`.
+///
+/// Blocks with `kind = "<kind>"` are deserialized as `C`, without the options common to all
+/// blocks (which are handled as for any other block). Their JSON Schemas are used by
+/// `yablocks describe`. `default_template` is used for blocks without a `template`, and should
+/// match what `C`'s [`BlockStreamConfig::to_stream`] falls back to. Kinds must be registered
+/// before the config is loaded.
+pub fn register_block<C, D>(kind: &'static str, default_template: &'static str) -> Result<()>
+where
+    C: BlockStreamConfig + DeserializeOwned + JsonSchema,
+    D: DeserializeOwned + Serialize + JsonSchema,
+{
+    fn check_config<C: DeserializeOwned>(
+        value: toml::Value,
+    ) -> std::result::Result<(), toml::de::Error> {
+        value.try_into::<C>().map(|_| ())
+    }
+    fn to_stream<C: BlockStreamConfig + DeserializeOwned>(
+        value: toml::Value,
+        name: String,
+    ) -> Result<BlockStream> {
+        value.try_into::<C>()?.to_stream(name)
+    }
+
+    if describe_kinds()
+        .iter()
+        .any(|description| description.kind == kind)
+    {
+        anyhow::bail!("Block kind `{kind}` already exists");
+    }
+    let custom_kind = CustomKind {
+        check_config: check_config::<C>,
+        to_stream: to_stream::<C>,
+        parse_data: parse::<D>,
+        describe: |kind| KindDescription {
+            kind,
+            inputs: schema_for::<C>(),
+            outputs: schema_for::<D>(),
+        },
+        default_template,
+    };
+    CUSTOM_KINDS.lock().unwrap().insert(kind, custom_kind);
+
+    Ok(())
+}
+
+fn custom_kind(kind: &str) -> Option<CustomKind> {
+    CUSTOM_KINDS.lock().unwrap().get(kind).copied()
+}
+
+pub fn is_custom_kind(kind: &str) -> bool {
+    custom_kind(kind).is_some()
+}
+
+/// Check the config of a block of a registered kind.
+pub fn check_custom_config(
+    kind: &str,
+    config: toml::Value,
+) -> std::result::Result<(), toml::de::Error> {
+    match custom_kind(kind) {
+        Some(custom_kind) => (custom_kind.check_config)(config),
+        None => Ok(()),
+    }
+}
+
+/// Check that a script block's script loads and compiles.
+pub fn check_script(config: &config::ScriptConfig) -> Result<()> {
+    script::Script::load(config).map(|_| ())
+}
+
+impl BlockStreamConfig for config::Block {
+    fn to_stream(self, name: String) -> Result<BlockStream> {
+        if self.options.template_file.is_some() && self.config.template().is_some() {
+            anyhow::bail!("Cannot use both template and template-file");
+        }
+        RENDERER.set_block_options(&name, &self.options);
+        let stream = self.config.to_stream(name.clone())?;
+        if let Some(file) = &self.options.template_file {
+            RENDERER.add_template_file(&name, file)?;
+        }
+        match self.options.marquee {
+            Some(marquee) => marquee::wrap(name, stream, marquee),
+            None => Ok(stream),
+        }
+    }
+}
+
 impl BlockStreamConfig for config::BlockConfig {
     fn to_stream(self, name: String) -> Result<BlockStream> {
         match self {
@@ -33,10 +152,221 @@ impl BlockStreamConfig for config::BlockConfig {
             config::BlockConfig::Network(config) => config.to_stream(name),
             config::BlockConfig::NetworkStats(config) => config.to_stream(name),
             config::BlockConfig::Noop(config) => config.to_stream(name),
+            config::BlockConfig::Plugin(config) => config.to_stream(name),
             config::BlockConfig::PulseVolume(config) => config.to_stream(name),
+            config::BlockConfig::Script(config) => config.to_stream(name),
             config::BlockConfig::Signal(config) => config.to_stream(name),
             config::BlockConfig::Stdin(config) => config.to_stream(name),
             config::BlockConfig::Temperature(config) => config.to_stream(name),
+            config::BlockConfig::Custom(config) => match custom_kind(&config.kind) {
+                Some(custom_kind) => (custom_kind.to_stream)(config.config, name),
+                None => anyhow::bail!("Unknown block kind `{}`", config.kind),
+            },
+        }
+    }
+}
+
+/// Render a block from recorded data instead of a live source.
+///
+/// Each item of `events` is rendered at the given time.
+pub fn replay_stream(
+    name: String,
+    block: config::Block,
+    events: Vec<(std::time::Instant, serde_json::Value)>,
+) -> Result<BlockStream> {
+    RENDERER.set_block_options(&name, &block.options);
+    add_template(&name, &block)?;
+    let stream = replay::stream(name.clone(), events);
+    match block.options.marquee {
+        Some(marquee) => marquee::wrap(name, stream, marquee),
+        None => Ok(stream),
+    }
+}
+
+/// Add a block's template, falling back to the default template for its kind.
+pub fn add_template(name: &str, block: &config::Block) -> Result<()> {
+    match (&block.options.template_file, block.config.template()) {
+        (Some(_), Some(_)) => anyhow::bail!("Cannot use both template and template-file"),
+        (Some(file), None) => RENDERER.add_template_file(name, file),
+        (None, Some(template)) => RENDERER.add_template(name, template),
+        (None, None) => match default_template(&block.config) {
+            Some(template) => RENDERER.add_template(name, template),
+            None => Ok(()),
+        },
+    }
+}
+
+fn default_template(config: &config::BlockConfig) -> Option<&'static str> {
+    match config {
+        config::BlockConfig::Command(_) => Some(command::DEFAULT_TEMPLATE),
+        config::BlockConfig::Cpu(_) => Some(cpu::DEFAULT_TEMPLATE),
+        config::BlockConfig::DateTime(_) => Some(datetime::DEFAULT_TEMPLATE),
+        config::BlockConfig::Interval(_) => Some(interval::DEFAULT_TEMPLATE),
+        config::BlockConfig::Inotify(_) => Some(inotify::DEFAULT_TEMPLATE),
+        config::BlockConfig::Network(_) => Some(network::DEFAULT_TEMPLATE),
+        config::BlockConfig::NetworkStats(_) => Some(network_stats::DEFAULT_TEMPLATE),
+        config::BlockConfig::Noop(_) => None,
+        config::BlockConfig::Plugin(_) => Some(plugin::DEFAULT_TEMPLATE),
+        config::BlockConfig::PulseVolume(_) => Some(pulse_volume::DEFAULT_TEMPLATE),
+        config::BlockConfig::Script(_) => Some(script::DEFAULT_TEMPLATE),
+        config::BlockConfig::Signal(_) => Some(signal::DEFAULT_TEMPLATE),
+        config::BlockConfig::Stdin(_) => Some(stdin::DEFAULT_TEMPLATE),
+        config::BlockConfig::Temperature(_) => Some(temperature::DEFAULT_TEMPLATE),
+        config::BlockConfig::Custom(config) => {
+            custom_kind(&config.kind).map(|custom_kind| custom_kind.default_template)
         }
     }
 }
+
+/// Check that `data` has the shape of the data blocks of `config`'s kind render with.
+pub fn parse_data(
+    config: &config::BlockConfig,
+    data: serde_json::Value,
+) -> Result<serde_json::Value> {
+    match config {
+        config::BlockConfig::Command(_) => parse::<command::BlockData>(data),
+        config::BlockConfig::Cpu(_) => parse::<cpu::BlockData>(data),
+        config::BlockConfig::DateTime(_) => parse::<datetime::BlockData>(data),
+        config::BlockConfig::Interval(_) => parse::<interval::BlockData>(data),
+        config::BlockConfig::Inotify(_) => parse::<inotify::BlockData>(data),
+        config::BlockConfig::Network(_) => parse::<network::BlockData>(data),
+        config::BlockConfig::NetworkStats(_) => parse::<network_stats::BlockData>(data),
+        config::BlockConfig::Noop(_) => parse::<()>(data),
+        config::BlockConfig::Plugin(_) => parse::<plugin::BlockData>(data),
+        config::BlockConfig::PulseVolume(_) => parse::<pulse_volume::BlockData>(data),
+        config::BlockConfig::Script(_) => parse::<script::BlockData>(data),
+        config::BlockConfig::Signal(_) => parse::<signal::BlockData>(data),
+        config::BlockConfig::Stdin(_) => parse::<stdin::BlockData>(data),
+        config::BlockConfig::Temperature(_) => parse::<temperature::BlockData>(data),
+        config::BlockConfig::Custom(config) => match custom_kind(&config.kind) {
+            Some(custom_kind) => (custom_kind.parse_data)(data),
+            None => anyhow::bail!("Unknown block kind `{}`", config.kind),
+        },
+    }
+}
+
+fn parse<T: DeserializeOwned + Serialize>(data: serde_json::Value) -> Result<serde_json::Value> {
+    Ok(serde_json::to_value(serde_json::from_value::<T>(data)?)?)
+}
+
+/// Describe the config and template data of every block kind.
+pub fn describe_kinds() -> Vec<KindDescription> {
+    let kind = |kind, inputs, outputs| KindDescription {
+        kind,
+        inputs,
+        outputs,
+    };
+    let mut kinds = vec![
+        kind(
+            "command",
+            schema_for::<config::CommandConfig>(),
+            schema_for::<command::BlockData>(),
+        ),
+        kind(
+            "cpu",
+            schema_for::<config::CpuConfig>(),
+            schema_for::<cpu::BlockData>(),
+        ),
+        kind(
+            "date-time",
+            schema_for::<config::DateTimeConfig>(),
+            schema_for::<datetime::BlockData>(),
+        ),
+        kind(
+            "interval",
+            schema_for::<config::IntervalConfig>(),
+            schema_for::<interval::BlockData>(),
+        ),
+        kind(
+            "inotify",
+            schema_for::<config::InotifyConfig>(),
+            schema_for::<inotify::BlockData>(),
+        ),
+        kind(
+            "network",
+            schema_for::<config::NetworkConfig>(),
+            schema_for::<network::BlockData>(),
+        ),
+        kind(
+            "network-stats",
+            schema_for::<config::NetworkStatsConfig>(),
+            schema_for::<network_stats::BlockData>(),
+        ),
+        kind(
+            "noop",
+            schema_for::<config::NoopConfig>(),
+            serde_json::json!({ "type": "object", "properties": {} }),
+        ),
+        kind(
+            "plugin",
+            schema_for::<config::PluginConfig>(),
+            schema_for::<plugin::BlockData>(),
+        ),
+        kind(
+            "pulse-volume",
+            schema_for::<config::PulseVolumeConfig>(),
+            schema_for::<pulse_volume::BlockData>(),
+        ),
+        kind(
+            "script",
+            schema_for::<config::ScriptConfig>(),
+            schema_for::<script::BlockData>(),
+        ),
+        kind(
+            "signal",
+            schema_for::<config::SignalConfig>(),
+            schema_for::<signal::BlockData>(),
+        ),
+        kind(
+            "stdin",
+            schema_for::<config::StdinConfig>(),
+            schema_for::<stdin::BlockData>(),
+        ),
+        kind(
+            "temperature",
+            schema_for::<config::TemperatureConfig>(),
+            schema_for::<temperature::BlockData>(),
+        ),
+    ];
+    for (kind, custom_kind) in CUSTOM_KINDS.lock().unwrap().iter() {
+        kinds.push((custom_kind.describe)(kind));
+    }
+
+    kinds
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(serde::Deserialize, JsonSchema)]
+    struct CounterConfig {
+        #[allow(dead_code)]
+        template: Option<String>,
+    }
+
+    impl BlockStreamConfig for CounterConfig {
+        fn to_stream(self, _name: String) -> Result<BlockStream> {
+            Ok(Box::pin(futures::stream::empty()))
+        }
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, JsonSchema)]
+    struct CounterData {
+        count: u64,
+    }
+
+    #[test]
+    fn custom_default_template() {
+        register_block::<CounterConfig, CounterData>("test-counter", "count {{ count }}").unwrap();
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(&mut file, b"[blocks.counter]\nkind = \"test-counter\"\n")
+            .unwrap();
+        let config = config::load_config(Some(file.path().to_path_buf())).unwrap();
+
+        let (name, block) = &config.blocks[0];
+        add_template(name, block).unwrap();
+        let output = RENDERER.render(name, CounterData { count: 3 }).unwrap();
+        assert_eq!(output, "count 3");
+    }
+}
@@ -5,8 +5,10 @@ use tokio::io::{AsyncBufReadExt, BufReader};
 use super::{BlockStream, BlockStreamConfig};
 use crate::RENDERER;
 
-#[derive(serde::Serialize, Debug, Clone)]
-struct BlockData {
+pub const DEFAULT_TEMPLATE: &str = "{{output}}";
+
+#[derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema, Debug, Clone)]
+pub struct BlockData {
     command: String,
     args: Vec<String>,
     output: serde_json::Value,
@@ -62,7 +64,9 @@ impl Block {
 
 impl BlockStreamConfig for crate::config::CommandConfig {
     fn to_stream(self, name: String) -> Result<BlockStream> {
-        let template = self.template.unwrap_or_else(|| "{{output}}".to_string());
+        let template = self
+            .template
+            .unwrap_or_else(|| DEFAULT_TEMPLATE.to_string());
         RENDERER.add_template(&name, &template)?;
 
         let block = Block::new(name, self.command, self.args, self.json)?;
@@ -1,3 +1,5 @@
+use std::path::{Path, PathBuf};
+
 use anyhow::Result;
 use futures::stream;
 
@@ -5,8 +7,10 @@ use super::{BlockStream, BlockStreamConfig};
 use crate::RENDERER;
 use procfs::{CpuTime, KernelStats};
 
-#[derive(serde::Serialize, Debug, Clone)]
-struct BlockData {
+pub const DEFAULT_TEMPLATE: &str = "{{cpu_times.non_idle | round(precision=1)}}";
+
+#[derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema, Debug, Clone)]
+pub struct BlockData {
     interval: u64,
     cpu_times: NormalizedCpuTimes,
 }
@@ -14,15 +18,18 @@ struct BlockData {
 struct Block {
     name: String,
     interval: u64,
+    stat_file: PathBuf,
     cpu_time: Option<CpuTime>,
 }
 
 impl Block {
-    fn new(name: String, interval: u64) -> Self {
+    fn new(name: String, interval: u64, proc_root: &Path) -> Self {
+        let stat_file = proc_root.join("stat");
         Self {
             name,
             interval,
-            cpu_time: KernelStats::new().map(|stats| stats.total).ok(),
+            cpu_time: read_cpu_time(&stat_file).ok(),
+            stat_file,
         }
     }
 
@@ -31,16 +38,16 @@ impl Block {
         let old_cpu_time = match &self.cpu_time {
             Some(cpu_time) => cpu_time,
             None => {
-                self.cpu_time = match KernelStats::new() {
-                    Ok(stats) => Some(stats.total),
-                    Err(e) => return Some(Err(anyhow::Error::from(e))),
+                self.cpu_time = match read_cpu_time(&self.stat_file) {
+                    Ok(cpu_time) => Some(cpu_time),
+                    Err(e) => return Some(Err(e)),
                 };
                 self.cpu_time.as_ref().unwrap()
             }
         };
-        let new_cpu_time = match KernelStats::new() {
-            Ok(stats) => stats.total,
-            Err(e) => return Some(Err(anyhow::Error::from(e))),
+        let new_cpu_time = match read_cpu_time(&self.stat_file) {
+            Ok(cpu_time) => cpu_time,
+            Err(e) => return Some(Err(e)),
         };
         let cpu_times = NormalizedCpuTimes::from_diff(old_cpu_time, &new_cpu_time);
         let data = BlockData {
@@ -58,10 +65,13 @@ impl BlockStreamConfig for crate::config::CpuConfig {
     fn to_stream(self, name: String) -> Result<BlockStream> {
         let template = self
             .template
-            .unwrap_or_else(|| "{{cpu_times.non_idle | round(precision=1)}}".to_string());
+            .unwrap_or_else(|| DEFAULT_TEMPLATE.to_string());
         RENDERER.add_template(&name, &template)?;
 
-        let block = Block::new(name, self.interval);
+        let proc_root = self
+            .proc_root
+            .unwrap_or_else(|| PathBuf::from(crate::config::DEFAULT_PROC_ROOT));
+        let block = Block::new(name, self.interval, &proc_root);
         let stream = stream::unfold(block, move |mut block| async {
             let result = block.wait_for_output().await?;
             Some(((block.name.clone(), result), block))
@@ -71,7 +81,12 @@ impl BlockStreamConfig for crate::config::CpuConfig {
     }
 }
 
-#[derive(serde::Serialize, Debug, Clone)]
+fn read_cpu_time(stat_file: &Path) -> Result<CpuTime> {
+    let file = std::fs::File::open(stat_file)?;
+    Ok(KernelStats::from_reader(file)?.total)
+}
+
+#[derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema, Debug, Clone)]
 struct NormalizedCpuTimes {
     non_idle: f64,
     user: f64,
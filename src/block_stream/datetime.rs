@@ -5,12 +5,14 @@ use chrono::{Datelike, Offset, TimeZone, Timelike, Utc};
 use chrono_tz::OffsetName;
 use futures::{stream, StreamExt};
 
-use super::{BlockStream, BlockStreamConfig};
+use super::{util, BlockStream, BlockStreamConfig};
 use crate::config::Precision;
 use crate::RENDERER;
 
-#[derive(serde::Serialize, Debug, Clone)]
-struct BlockData {
+pub const DEFAULT_TEMPLATE: &str = "{{hour}}:{{minute}}";
+
+#[derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema, Debug, Clone)]
+pub struct BlockData {
     timestamp: i64,
     year: i32,
     month: u32,
@@ -91,7 +93,7 @@ impl BlockStreamConfig for crate::config::DateTimeConfig {
     fn to_stream(self, name: String) -> Result<BlockStream> {
         let template = self
             .template
-            .unwrap_or_else(|| "{{hour}}:{{minute}}".to_string());
+            .unwrap_or_else(|| DEFAULT_TEMPLATE.to_string());
         RENDERER.add_template(&name, &template)?;
 
         let block = Block {
@@ -116,12 +118,6 @@ impl BlockStreamConfig for crate::config::DateTimeConfig {
 }
 
 fn next_boundary(precision: Precision) -> Duration {
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default();
-    let now_secs = now.as_secs();
-    let now_nanos = now.subsec_nanos();
-
     let interval = match precision {
         Precision::Second => 1,
         Precision::Minute => 60,
@@ -129,10 +125,5 @@ fn next_boundary(precision: Precision) -> Duration {
         Precision::Day => 86400,
     };
 
-    let elapsed_within_interval = now_secs % interval;
-    let elapsed_nanos = elapsed_within_interval * 1_000_000_000 + now_nanos as u64;
-    let interval_nanos = interval * 1_000_000_000;
-    let remaining_nanos = interval_nanos - elapsed_nanos;
-
-    Duration::from_nanos(remaining_nanos % interval_nanos)
+    util::next_boundary(interval).unwrap_or_default()
 }
@@ -7,10 +7,12 @@ use notify::Watcher;
 use super::{BlockStream, BlockStreamConfig};
 use crate::RENDERER;
 
+pub const DEFAULT_TEMPLATE: &str = "{{contents}}";
+
 static DEBOUNCE_TIME: std::time::Duration = std::time::Duration::from_millis(10);
 
-#[derive(serde::Serialize, Debug, Clone)]
-struct BlockData {
+#[derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema, Debug, Clone)]
+pub struct BlockData {
     file: String,
     contents: serde_json::Value,
 }
@@ -69,7 +71,9 @@ impl Block {
 
 impl BlockStreamConfig for crate::config::InotifyConfig {
     fn to_stream<'a>(self, name: String) -> Result<BlockStream> {
-        let template = self.template.unwrap_or_else(|| "{{contents}}".to_string());
+        let template = self
+            .template
+            .unwrap_or_else(|| DEFAULT_TEMPLATE.to_string());
         RENDERER.add_template(&name, &template)?;
 
         let block = Block::new(name.clone(), self.file.clone(), self.json)?;
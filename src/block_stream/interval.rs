@@ -2,14 +2,17 @@ use anyhow::Result;
 use futures::{stream, StreamExt};
 use tokio::process::Command;
 
-use super::{BlockStream, BlockStreamConfig};
+use super::{util, BlockStream, BlockStreamConfig};
+use crate::config::Schedule;
 use crate::RENDERER;
 
-#[derive(serde::Serialize, Debug, Clone)]
-struct BlockData<'a> {
-    command: &'a str,
-    args: &'a Vec<String>,
-    interval: u64,
+pub const DEFAULT_TEMPLATE: &str = "{{output}}";
+
+#[derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema, Debug, Clone)]
+pub struct BlockData {
+    command: String,
+    args: Vec<String>,
+    interval: Option<u64>,
     status: i32,
     output: serde_json::Value,
 }
@@ -19,13 +22,15 @@ struct Block {
     name: String,
     command: String,
     args: Vec<String>,
-    interval: u64,
+    interval: Option<u64>,
+    schedule: Option<Schedule>,
+    align: bool,
     json: bool,
 }
 
 impl Block {
     async fn wait_for_output(&self) -> Option<Result<String>> {
-        tokio::time::sleep(std::time::Duration::from_secs(self.interval)).await;
+        tokio::time::sleep(self.next_run()?).await;
         Some(
             render_output(
                 &self.name,
@@ -37,11 +42,28 @@ impl Block {
             .await,
         )
     }
+
+    /// Time until the command should next run, or `None` if the schedule has no more runs.
+    fn next_run(&self) -> Option<std::time::Duration> {
+        if let Some(schedule) = &self.schedule {
+            let next = schedule.0.upcoming(chrono::Local).next()?;
+            return Some((next - chrono::Local::now()).to_std().unwrap_or_default());
+        }
+        let interval = self.interval?;
+        if self.align {
+            util::next_boundary(interval)
+        } else {
+            Some(std::time::Duration::from_secs(interval))
+        }
+    }
 }
 
 impl BlockStreamConfig for crate::config::IntervalConfig {
     fn to_stream(self, name: String) -> Result<BlockStream> {
-        let template = self.template.unwrap_or_else(|| "{{output}}".to_string());
+        self.validate()?;
+        let template = self
+            .template
+            .unwrap_or_else(|| DEFAULT_TEMPLATE.to_string());
         RENDERER.add_template(&name, &template)?;
 
         let block = Block {
@@ -49,6 +71,8 @@ impl BlockStreamConfig for crate::config::IntervalConfig {
             command: self.command.clone(),
             args: self.args.clone(),
             interval: self.interval,
+            schedule: self.schedule,
+            align: self.align,
             json: self.json,
         };
         let first_run = stream::once(async move {
@@ -69,7 +93,7 @@ async fn render_output(
     name: &str,
     command: &str,
     args: &Vec<String>,
-    interval: u64,
+    interval: Option<u64>,
     json: bool,
 ) -> Result<String> {
     let process_output = Command::new(command).args(args).output().await?;
@@ -80,8 +104,8 @@ async fn render_output(
         serde_json::json!(String::from_utf8_lossy(&process_output.stdout).trim())
     };
     let data = BlockData {
-        command,
-        args,
+        command: command.to_string(),
+        args: args.clone(),
         interval,
         status,
         output,
@@ -0,0 +1,135 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use futures::{stream, StreamExt};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use super::BlockStream;
+use crate::config::MarqueeConfig;
+
+struct Marquee {
+    name: String,
+    stream: BlockStream,
+    width: usize,
+    /// Ticks when the text should scroll, independently of updates from `stream`.
+    timer: tokio::time::Interval,
+    separator: String,
+    text: String,
+    /// The characters to scroll through, if the text is too wide to fit.
+    chars: Option<Vec<char>>,
+    offset: usize,
+}
+
+impl Marquee {
+    async fn wait_for_output(&mut self) -> Option<(String, Result<String>)> {
+        tokio::select! {
+            item = self.stream.next() => {
+                let (name, result) = item?;
+                Some((name, result.map(|output| self.set_text(output))))
+            }
+            _ = self.timer.tick(), if self.chars.is_some() => {
+                self.offset += 1;
+                Some((self.name.clone(), Ok(self.window())))
+            }
+        }
+    }
+
+    fn set_text(&mut self, text: String) -> String {
+        if text.width() <= self.width {
+            self.chars = None;
+            return text;
+        }
+        // Keep scrolling from the same place if the text hasn't changed.
+        if text != self.text || self.chars.is_none() {
+            self.chars = Some(text.chars().chain(self.separator.chars()).collect());
+            self.offset = 0;
+            self.text = text;
+            self.timer.reset();
+        }
+        self.window()
+    }
+
+    /// The visible part of the scrolling text.
+    fn window(&self) -> String {
+        let chars = match &self.chars {
+            Some(chars) if !chars.is_empty() => chars,
+            _ => return self.text.clone(),
+        };
+        let mut window = String::new();
+        let mut width = 0;
+        let start = self.offset % chars.len();
+        for c in chars.iter().cycle().skip(start).take(chars.len()) {
+            let char_width = c.width().unwrap_or(0);
+            if width + char_width > self.width {
+                break;
+            }
+            window.push(*c);
+            width += char_width;
+        }
+        window.push_str(&" ".repeat(self.width - width));
+        window
+    }
+}
+
+/// Scroll the output of `stream` when it's wider than the configured width.
+pub fn wrap(name: String, stream: BlockStream, config: MarqueeConfig) -> Result<BlockStream> {
+    let interval = Duration::try_from_secs_f64(config.interval)
+        .ok()
+        .filter(|interval| !interval.is_zero())
+        .ok_or_else(|| anyhow::anyhow!("Marquee interval must be a positive number of seconds"))?;
+    let mut timer = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+    timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let marquee = Marquee {
+        name,
+        stream,
+        width: config.width,
+        timer,
+        separator: config.separator,
+        text: String::new(),
+        chars: None,
+        offset: 0,
+    };
+    let stream = stream::unfold(marquee, |mut marquee| async {
+        let item = marquee.wait_for_output().await?;
+        Some((item, marquee))
+    });
+
+    Ok(Box::pin(stream))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(interval: f64) -> MarqueeConfig {
+        MarqueeConfig {
+            width: 3,
+            interval,
+            separator: " ".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn scrolls_while_block_repeats_output() {
+        // The block re-emits the same text more often than the marquee scrolls.
+        let inner = stream::unfold((), |()| async {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            Some((("title".to_string(), Ok("abcdef".to_string())), ()))
+        });
+        let marquee = wrap("title".to_string(), Box::pin(inner), config(0.05)).unwrap();
+        let windows: Vec<String> = marquee
+            .take_until(tokio::time::sleep(Duration::from_millis(300)))
+            .map(|(_, output)| output.unwrap())
+            .collect()
+            .await;
+        assert!(windows.contains(&"bcd".to_string()), "{windows:?}");
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_intervals() {
+        for interval in [0.0, -1.0, f64::NAN, f64::INFINITY, 1e300] {
+            let inner = stream::empty();
+            assert!(wrap("title".to_string(), Box::pin(inner), config(interval)).is_err());
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use std::net::IpAddr;
+use std::path::{Path, PathBuf};
 
 use anyhow::Result;
 use futures::{stream, StreamExt, TryStreamExt};
@@ -15,6 +16,8 @@ use rtnetlink::sys::{AsyncSocket, SocketAddr};
 use super::{BlockStream, BlockStreamConfig};
 use crate::RENDERER;
 
+pub const DEFAULT_TEMPLATE: &str = "{{operstate}}";
+
 static NL_GRP: u32 = 1 << (RTNLGRP_LINK - 1)
     | 1 << (RTNLGRP_IPV4_IFADDR - 1)
     | 1 << (RTNLGRP_IPV6_IFADDR - 1)
@@ -27,6 +30,8 @@ type Receiver =
 struct Block {
     name: String,
     device: String,
+    proc_root: PathBuf,
+    sys_root: PathBuf,
     messages: Receiver,
 }
 
@@ -37,8 +42,8 @@ struct WifiInfo {
     frequency: Option<u32>,
 }
 
-#[derive(serde::Serialize, Debug, Clone)]
-struct BlockData {
+#[derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema, Debug, Clone)]
+pub struct BlockData {
     device: String,
     operstate: String,
     wireless: bool,
@@ -81,8 +86,8 @@ impl IpVersion {
 
     fn route_file(&self) -> &'static str {
         match self {
-            IpVersion::V4 => "/proc/net/route",
-            IpVersion::V6 => "/proc/net/ipv6_route",
+            IpVersion::V4 => "net/route",
+            IpVersion::V6 => "net/ipv6_route",
         }
     }
 
@@ -159,7 +164,8 @@ impl Block {
         loop {
             let (message, _) = self.messages.next().await?;
             if let Some(ifindex) = self.parse_message(message) {
-                let data = BlockData::read(ifindex, &self.device).await;
+                let data =
+                    BlockData::read(ifindex, &self.device, &self.proc_root, &self.sys_root).await;
                 return Some(RENDERER.render(&self.name, data));
             }
         }
@@ -167,13 +173,13 @@ impl Block {
 }
 
 impl BlockData {
-    async fn read(ifindex: u32, device: &str) -> Self {
-        let operstate = Self::get_operstate(device).await;
+    async fn read(ifindex: u32, device: &str, proc_root: &Path, sys_root: &Path) -> Self {
+        let operstate = Self::get_operstate(device, sys_root).await;
         let wireless_info = Self::get_wireless_info(device).ok().flatten();
         let ipv4_addresses = Self::get_ip_addresses(ifindex, IpVersion::V4).await;
         let ipv6_addresses = Self::get_ip_addresses(ifindex, IpVersion::V6).await;
-        let ipv4_gateway = Self::get_gateway(device, IpVersion::V4).await;
-        let ipv6_gateway = Self::get_gateway(device, IpVersion::V6).await;
+        let ipv4_gateway = Self::get_gateway(device, proc_root, IpVersion::V4).await;
+        let ipv6_gateway = Self::get_gateway(device, proc_root, IpVersion::V6).await;
 
         let (essid, quality, signal_dbm, frequency) = wireless_info
             .map_or((None, None, None, None), |w| {
@@ -195,8 +201,8 @@ impl BlockData {
         }
     }
 
-    async fn get_operstate(device: &str) -> String {
-        let path: std::path::PathBuf = ["/sys/class/net", device, "operstate"].iter().collect();
+    async fn get_operstate(device: &str, sys_root: &Path) -> String {
+        let path = sys_root.join("class/net").join(device).join("operstate");
         tokio::fs::read_to_string(path)
             .await
             .map(|s| s.trim().to_string())
@@ -251,8 +257,10 @@ impl BlockData {
         addresses
     }
 
-    async fn get_gateway(device: &str, version: IpVersion) -> Option<IpAddr> {
-        let content = tokio::fs::read_to_string(version.route_file()).await.ok()?;
+    async fn get_gateway(device: &str, proc_root: &Path, version: IpVersion) -> Option<IpAddr> {
+        let content = tokio::fs::read_to_string(proc_root.join(version.route_file()))
+            .await
+            .ok()?;
         for line in content.lines() {
             let parts: Vec<&str> = line.split_whitespace().collect();
             if parts.len() >= 3 && parts[0] == device && parts[1] == version.default_route_prefix()
@@ -268,7 +276,9 @@ impl BlockData {
 
 impl BlockStreamConfig for crate::config::NetworkConfig {
     fn to_stream(self, name: String) -> Result<BlockStream> {
-        let template = self.template.unwrap_or_else(|| "{{operstate}}".to_string());
+        let template = self
+            .template
+            .unwrap_or_else(|| DEFAULT_TEMPLATE.to_string());
         RENDERER.add_template(&name, &template)?;
 
         let (mut conn, _, messages) = rtnetlink::new_connection()?;
@@ -276,8 +286,15 @@ impl BlockStreamConfig for crate::config::NetworkConfig {
         conn.socket_mut().socket_mut().bind(&addr)?;
         tokio::spawn(conn);
 
+        let proc_root = self
+            .proc_root
+            .unwrap_or_else(|| PathBuf::from(crate::config::DEFAULT_PROC_ROOT));
+        let sys_root = self
+            .sys_root
+            .unwrap_or_else(|| PathBuf::from(crate::config::DEFAULT_SYS_ROOT));
         let device = self.device.clone();
-        let ifindex = match std::fs::read_to_string(format!("/sys/class/net/{device}/ifindex")) {
+        let ifindex_file = sys_root.join("class/net").join(&device).join("ifindex");
+        let ifindex = match std::fs::read_to_string(ifindex_file) {
             Ok(s) => match s.trim().parse() {
                 Ok(i) => i,
                 Err(_) => return Err(anyhow::anyhow!("failed to parse ifindex")),
@@ -285,8 +302,9 @@ impl BlockStreamConfig for crate::config::NetworkConfig {
             Err(_) => return Err(anyhow::anyhow!("failed to read ifindex")),
         };
         let name_clone = name.clone();
+        let (first_proc_root, first_sys_root) = (proc_root.clone(), sys_root.clone());
         let first_run = stream::once(async move {
-            let data = BlockData::read(ifindex, &device).await;
+            let data = BlockData::read(ifindex, &device, &first_proc_root, &first_sys_root).await;
             let result = RENDERER.render(&name_clone, data);
             (name_clone, result)
         });
@@ -294,6 +312,8 @@ impl BlockStreamConfig for crate::config::NetworkConfig {
         let block = Block {
             name: name.clone(),
             device: self.device.clone(),
+            proc_root,
+            sys_root,
             messages,
         };
         let stream = stream::unfold(block, move |mut block| async {
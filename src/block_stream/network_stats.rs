@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use anyhow::Result;
 use futures::stream;
@@ -6,8 +6,10 @@ use futures::stream;
 use super::{BlockStream, BlockStreamConfig};
 use crate::RENDERER;
 
-#[derive(serde::Serialize, Debug, Clone)]
-struct BlockData {
+pub const DEFAULT_TEMPLATE: &str = "{{rx_bytes_per_sec}} ↓ {{tx_bytes_per_sec}} ↑";
+
+#[derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema, Debug, Clone)]
+pub struct BlockData {
     rx_bytes_per_sec: f64,
     tx_bytes_per_sec: f64,
     rx_packets_per_sec: f64,
@@ -18,6 +20,7 @@ struct Block {
     name: String,
     device: String,
     interval: u64,
+    sys_root: PathBuf,
     prev_stats: Option<NetworkStats>,
 }
 
@@ -30,11 +33,12 @@ struct NetworkStats {
 }
 
 impl Block {
-    fn new(name: String, device: String, interval: u64) -> Self {
+    fn new(name: String, device: String, interval: u64, sys_root: PathBuf) -> Self {
         Self {
             name,
             device,
             interval,
+            sys_root,
             prev_stats: None,
         }
     }
@@ -42,7 +46,7 @@ impl Block {
     async fn wait_for_output(&mut self) -> Option<Result<String>> {
         tokio::time::sleep(std::time::Duration::from_secs(self.interval)).await;
 
-        let new_stats = match NetworkStats::read(&self.device).await {
+        let new_stats = match NetworkStats::read(&self.sys_root, &self.device).await {
             Ok(s) => s,
             Err(e) => return Some(Err(e)),
         };
@@ -77,8 +81,8 @@ impl Block {
 }
 
 impl NetworkStats {
-    async fn read(device: &str) -> Result<Self> {
-        let base: PathBuf = ["/sys/class/net", device, "statistics"].iter().collect();
+    async fn read(sys_root: &Path, device: &str) -> Result<Self> {
+        let base = sys_root.join("class/net").join(device).join("statistics");
 
         let rx_bytes = tokio::fs::read_to_string(base.join("rx_bytes"))
             .await?
@@ -110,10 +114,13 @@ impl BlockStreamConfig for crate::config::NetworkStatsConfig {
     fn to_stream(self, name: String) -> Result<BlockStream> {
         let template = self
             .template
-            .unwrap_or_else(|| "{{rx_bytes_per_sec}} ↓ {{tx_bytes_per_sec}} ↑".to_string());
+            .unwrap_or_else(|| DEFAULT_TEMPLATE.to_string());
         RENDERER.add_template(&name, &template)?;
 
-        let block = Block::new(name.clone(), self.device.clone(), self.interval);
+        let sys_root = self
+            .sys_root
+            .unwrap_or_else(|| PathBuf::from(crate::config::DEFAULT_SYS_ROOT));
+        let block = Block::new(name.clone(), self.device.clone(), self.interval, sys_root);
         let stream = stream::unfold(block, move |mut block| async {
             let result = block.wait_for_output().await?;
             Some(((block.name.clone(), result), block))
@@ -0,0 +1,197 @@
+use std::process::Stdio;
+
+use anyhow::{Context, Result};
+use futures::channel::mpsc::{self, UnboundedSender};
+use futures::{stream, StreamExt};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::signal::unix::{signal, SignalKind};
+
+use super::{stdin, BlockStream, BlockStreamConfig};
+use crate::RENDERER;
+
+pub const DEFAULT_TEMPLATE: &str = "{{data}}";
+
+#[derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema, Debug, Clone)]
+pub struct BlockData {
+    data: serde_json::Value,
+}
+
+/// A message from yablocks to the plugin.
+#[derive(serde::Serialize, Debug)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+enum Request {
+    Config {
+        name: String,
+        config: serde_json::Value,
+    },
+    Refresh,
+    Click {
+        event: serde_json::Value,
+    },
+}
+
+/// A message from the plugin to yablocks.
+#[derive(serde::Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+enum Message {
+    Data { data: serde_json::Value },
+    Error { message: String },
+}
+
+struct Block {
+    name: String,
+    lines: tokio::io::Lines<BufReader<tokio::process::ChildStdout>>,
+    /// The plugin process, until it's been reported as exited.
+    child: Option<tokio::process::Child>,
+}
+
+impl Block {
+    async fn wait_for_output(&mut self) -> Option<Result<String>> {
+        let line = match self.lines.next_line().await {
+            Ok(Some(line)) => line,
+            Ok(None) => {
+                // Report how the plugin exited once, then end the stream.
+                let mut child = self.child.take()?;
+                return Some(match child.wait().await {
+                    Ok(status) => Err(anyhow::anyhow!("Plugin exited ({status})")),
+                    Err(e) => Err(anyhow::Error::from(e)),
+                });
+            }
+            Err(e) => return Some(Err(anyhow::Error::from(e))),
+        };
+        let message = match serde_json::from_str(&line) {
+            Ok(message) => message,
+            Err(e) => {
+                return Some(Err(
+                    anyhow::Error::from(e).context(format!("Invalid message from plugin: {line}"))
+                ))
+            }
+        };
+        match message {
+            Message::Data { data } => Some(RENDERER.render(&self.name, BlockData { data })),
+            Message::Error { message } => Some(Err(anyhow::anyhow!(message))),
+        }
+    }
+}
+
+impl BlockStreamConfig for crate::config::PluginConfig {
+    fn to_stream(self, name: String) -> Result<BlockStream> {
+        let template = self
+            .template
+            .unwrap_or_else(|| DEFAULT_TEMPLATE.to_string());
+        RENDERER.add_template(&name, &template)?;
+
+        let mut child = tokio::process::Command::new(&self.command)
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("Failed to run {}", self.command))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| anyhow::anyhow!("Failed to open stdout for {name}"))?;
+        let mut stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| anyhow::anyhow!("Failed to open stdin for {name}"))?;
+
+        let (tx, mut rx) = mpsc::unbounded();
+        let _ = tx.unbounded_send(Request::Config {
+            name: name.clone(),
+            config: serde_json::to_value(&self.config)?,
+        });
+        let plugin_name = name.clone();
+        tokio::spawn(async move {
+            while let Some(request) = rx.next().await {
+                let mut line =
+                    serde_json::to_string(&request).expect("Failed to serialize request");
+                line.push('\n');
+                if let Err(error) = stdin.write_all(line.as_bytes()).await {
+                    eprintln!("Failed to write to plugin {plugin_name}: {error}");
+                    return;
+                }
+            }
+        });
+        if let Some(interval) = self.interval {
+            spawn_interval_refresh(interval, tx.clone());
+        }
+        if let Some(num) = self.signal {
+            spawn_signal_refresh(signal(SignalKind::from_raw(num.0))?, tx.clone());
+        }
+        if self.clicks {
+            spawn_click_forwarder(name.clone(), tx);
+        }
+
+        let block = Block {
+            name,
+            lines: BufReader::new(stdout).lines(),
+            child: Some(child),
+        };
+        let stream = stream::unfold(block, move |mut block| async {
+            let result = block.wait_for_output().await?;
+            Some(((block.name.clone(), result), block))
+        });
+
+        Ok(Box::pin(stream))
+    }
+}
+
+fn spawn_interval_refresh(interval: u64, tx: UnboundedSender<Request>) {
+    tokio::spawn(async move {
+        let period = std::time::Duration::from_secs(interval);
+        let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+        loop {
+            interval.tick().await;
+            if tx.unbounded_send(Request::Refresh).is_err() {
+                return;
+            }
+        }
+    });
+}
+
+fn spawn_signal_refresh(mut signal: tokio::signal::unix::Signal, tx: UnboundedSender<Request>) {
+    tokio::spawn(async move {
+        while signal.recv().await.is_some() {
+            if tx.unbounded_send(Request::Refresh).is_err() {
+                return;
+            }
+        }
+    });
+}
+
+/// Forward i3bar click events for the block `name` to the plugin.
+fn spawn_click_forwarder(name: String, tx: UnboundedSender<Request>) {
+    let mut lines = stdin::subscribe();
+    tokio::spawn(async move {
+        while let Some(result) = lines.next().await {
+            let line = match result {
+                Ok(line) => line,
+                Err(error) => {
+                    eprintln!("{error}");
+                    continue;
+                }
+            };
+            if let Some(event) = parse_click(&line, &name) {
+                if tx.unbounded_send(Request::Click { event }).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+}
+
+/// Parse a line of the i3bar click event stream, if it's a click on the block `name`.
+///
+/// The stream is an infinite JSON array, so the first line is `[` and later events are
+/// preceded by a comma.
+fn parse_click(line: &str, name: &str) -> Option<serde_json::Value> {
+    let line = line.trim().trim_start_matches(['[', ',']).trim_start();
+    let event: serde_json::Value = serde_json::from_str(line).ok()?;
+    if event.get("name")?.as_str()? == name {
+        Some(event)
+    } else {
+        None
+    }
+}
@@ -17,8 +17,10 @@ use pulse::volume::Volume;
 use super::{util::send_or_eprint, BlockStream, BlockStreamConfig};
 use crate::RENDERER;
 
-#[derive(serde::Serialize, Debug, Clone)]
-struct BlockData {
+pub const DEFAULT_TEMPLATE: &str = "{{volume}}";
+
+#[derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema, Debug, Clone)]
+pub struct BlockData {
     sink_name: String,
     volume: u32,
     muted: bool,
@@ -41,7 +43,9 @@ impl Block {
 
 impl BlockStreamConfig for crate::config::PulseVolumeConfig {
     fn to_stream(self, name: String) -> Result<BlockStream> {
-        let template = self.template.unwrap_or_else(|| "{{volume}}".to_string());
+        let template = self
+            .template
+            .unwrap_or_else(|| DEFAULT_TEMPLATE.to_string());
         RENDERER.add_template(&name, &template)?;
 
         let (tx, rx) = futures::channel::mpsc::channel::<Result<BlockData>>(1);
@@ -69,7 +73,7 @@ impl PulseVolumeMonitor {
         proplist
             .set_str(
                 pulse::proplist::properties::APPLICATION_NAME,
-                env!("CARGO_BIN_NAME"),
+                env!("CARGO_PKG_NAME"),
             )
             .map_err(|_| anyhow::anyhow!("Failed to build proplist"))?;
         let mainloop =
@@ -79,7 +83,7 @@ impl PulseVolumeMonitor {
         let context = Rc::new(RefCell::new(
             Context::new_with_proplist(
                 mainloop.borrow().deref(),
-                concat!(env!("CARGO_BIN_NAME"), "Context"),
+                concat!(env!("CARGO_PKG_NAME"), "Context"),
                 &proplist,
             )
             .ok_or_else(|| anyhow::anyhow!("Failed to get pulse audio context"))?,
@@ -0,0 +1,20 @@
+use std::time::Instant;
+
+use futures::{stream, StreamExt};
+use serde_json::Value;
+
+use super::BlockStream;
+use crate::RENDERER;
+
+pub fn stream(name: String, events: Vec<(Instant, Value)>) -> BlockStream {
+    let stream = stream::iter(events).then(move |(time, data)| {
+        let name = name.clone();
+        async move {
+            tokio::time::sleep_until(time.into()).await;
+            let result = RENDERER.render(&name, data);
+            (name, result)
+        }
+    });
+
+    Box::pin(stream)
+}
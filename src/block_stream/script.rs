@@ -0,0 +1,236 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use futures::{stream, StreamExt};
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Map, AST};
+use tokio::signal::unix::{signal, SignalKind};
+
+use super::{BlockStream, BlockStreamConfig};
+use crate::RENDERER;
+
+pub const DEFAULT_TEMPLATE: &str = "{{output}}";
+
+/// Limits on how much work a script can do, so a buggy script can't spin forever.
+const MAX_OPERATIONS: u64 = 100_000_000;
+const MAX_CALL_LEVELS: usize = 64;
+
+type ScriptResult<T> = std::result::Result<T, Box<EvalAltResult>>;
+
+#[derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema, Debug, Clone)]
+pub struct BlockData {
+    output: serde_json::Value,
+}
+
+/// A compiled script, along with the engine to run it.
+pub struct Script {
+    engine: Engine,
+    ast: AST,
+    timeout: Duration,
+    /// When the current run has to finish by.
+    deadline: Arc<Mutex<Instant>>,
+}
+
+impl Script {
+    pub fn load(config: &crate::config::ScriptConfig) -> Result<Self> {
+        let source = match (&config.script, &config.script_file) {
+            (Some(source), None) => source.clone(),
+            (None, Some(file)) => std::fs::read_to_string(file)
+                .with_context(|| format!("Failed to read {}", file.display()))?,
+            (Some(_), Some(_)) => anyhow::bail!("Cannot set both script and script-file"),
+            (None, None) => anyhow::bail!("One of script or script-file is required"),
+        };
+        let timeout = Duration::try_from_secs_f64(config.timeout)
+            .ok()
+            .filter(|timeout| !timeout.is_zero())
+            .ok_or_else(|| anyhow::anyhow!("Invalid timeout {}", config.timeout))?;
+        let deadline = Arc::new(Mutex::new(Instant::now()));
+        let engine = engine(deadline.clone());
+        let ast = engine
+            .compile(source)
+            .map_err(|e| anyhow::anyhow!("Failed to compile script: {e}"))?;
+
+        Ok(Self {
+            engine,
+            ast,
+            timeout,
+            deadline,
+        })
+    }
+
+    fn run(&self) -> Result<serde_json::Value> {
+        *self.deadline.lock().unwrap() = Instant::now() + self.timeout;
+        let result: Dynamic = self.engine.eval_ast(&self.ast).map_err(|e| match *e {
+            EvalAltResult::ErrorTerminated(..) => {
+                anyhow::anyhow!("Script timed out after {:?}", self.timeout)
+            }
+            _ => anyhow::anyhow!("Script failed: {e}"),
+        })?;
+        rhai::serde::from_dynamic(&result)
+            .map_err(|e| anyhow::anyhow!("Invalid script result: {e}"))
+    }
+}
+
+impl BlockStreamConfig for crate::config::ScriptConfig {
+    fn to_stream(self, name: String) -> Result<BlockStream> {
+        let template = self
+            .template
+            .clone()
+            .unwrap_or_else(|| DEFAULT_TEMPLATE.to_string());
+        RENDERER.add_template(&name, &template)?;
+        let script = Arc::new(Script::load(&self)?);
+
+        let mut triggers: Vec<stream::BoxStream<'static, ()>> = vec![];
+        if let Some(interval) = self.interval {
+            let period = std::time::Duration::from_secs(interval);
+            let mut interval =
+                tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            triggers.push(Box::pin(stream::unfold(interval, |mut interval| async {
+                interval.tick().await;
+                Some(((), interval))
+            })));
+        }
+        if let Some(num) = self.signal {
+            let signal = signal(SignalKind::from_raw(num.0))?;
+            triggers.push(Box::pin(stream::unfold(signal, |mut signal| async {
+                signal.recv().await?;
+                Some(((), signal))
+            })));
+        }
+        let stream = stream::once(async {})
+            .chain(stream::select_all(triggers))
+            .then(move |()| {
+                let name = name.clone();
+                let script = script.clone();
+                async move {
+                    let output = render_output(&name, script).await;
+                    (name, output)
+                }
+            });
+
+        Ok(Box::pin(stream))
+    }
+}
+
+async fn render_output(name: &str, script: Arc<Script>) -> Result<String> {
+    // Scripts can run commands and read files, so keep them off the async runtime.
+    let output = tokio::task::spawn_blocking(move || script.run()).await??;
+    RENDERER.render(name, BlockData { output })
+}
+
+/// Build a script engine with helpers for reading system state.
+///
+/// Scripts, and commands they run, are stopped once `deadline` has passed.
+fn engine(deadline: Arc<Mutex<Instant>>) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.set_max_call_levels(MAX_CALL_LEVELS);
+    // stdout is the bar's output, so keep `print` and `debug` out of it.
+    engine.on_print(|text| eprintln!("{text}"));
+    engine.on_debug(|text, _, pos| eprintln!("{pos:?}: {text}"));
+    let progress_deadline = deadline.clone();
+    engine.on_progress(move |_| {
+        (Instant::now() > *progress_deadline.lock().unwrap()).then_some(Dynamic::UNIT)
+    });
+    engine.register_fn("read_file", read_file);
+    engine.register_fn("glob", glob);
+    engine.register_fn("regex_match", regex_match);
+    engine.register_fn("regex_captures", regex_captures);
+    let run_deadline = deadline.clone();
+    engine.register_fn("run", move |command: &str| {
+        run(command, Array::new(), *run_deadline.lock().unwrap())
+    });
+    engine.register_fn("run", move |command: &str, args: Array| {
+        run(command, args, *deadline.lock().unwrap())
+    });
+    engine.register_fn("parse_json", parse_json);
+    engine
+}
+
+fn read_file(path: &str) -> ScriptResult<String> {
+    std::fs::read_to_string(path).map_err(|e| format!("Failed to read {path}: {e}").into())
+}
+
+fn glob(pattern: &str) -> ScriptResult<Array> {
+    let paths = glob::glob(pattern).map_err(|e| e.to_string())?;
+    let mut matches = Array::new();
+    for path in paths {
+        let path = path.map_err(|e| e.to_string())?;
+        matches.push(path.to_string_lossy().into_owned().into());
+    }
+    Ok(matches)
+}
+
+fn regex(pattern: &str) -> ScriptResult<regex::Regex> {
+    regex::Regex::new(pattern).map_err(|e| e.to_string().into())
+}
+
+fn regex_match(text: &str, pattern: &str) -> ScriptResult<bool> {
+    Ok(regex(pattern)?.is_match(text))
+}
+
+/// The capture groups of the first match of `pattern`, or `()` if there's no match.
+fn regex_captures(text: &str, pattern: &str) -> ScriptResult<Dynamic> {
+    let Some(captures) = regex(pattern)?.captures(text) else {
+        return Ok(Dynamic::UNIT);
+    };
+    let groups: Array = captures
+        .iter()
+        .map(|group| match group {
+            Some(group) => group.as_str().into(),
+            None => Dynamic::UNIT,
+        })
+        .collect();
+    Ok(groups.into())
+}
+
+/// Run a command, returning its `status`, `stdout` and `stderr`.
+///
+/// The command is killed if it's still running at `deadline`.
+fn run(command: &str, args: Array, deadline: Instant) -> ScriptResult<Map> {
+    let args: Vec<String> = args.into_iter().map(|arg| arg.to_string()).collect();
+    let mut child = std::process::Command::new(command)
+        .args(&args)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run {command}: {e}"))?;
+    // Read both pipes while waiting, so a chatty command can't block on a full pipe.
+    let stdout = read_pipe(child.stdout.take());
+    let stderr = read_pipe(child.stderr.take());
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(10)),
+            Ok(None) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("Timed out running {command}").into());
+            }
+            Err(e) => return Err(format!("Failed to run {command}: {e}").into()),
+        }
+    };
+    let mut result = Map::new();
+    // Commands killed by a signal have no exit code.
+    result.insert("status".into(), status.code().map_or(-1, i64::from).into());
+    result.insert("stdout".into(), stdout.join().unwrap_or_default().into());
+    result.insert("stderr".into(), stderr.join().unwrap_or_default().into());
+    Ok(result)
+}
+
+fn read_pipe(pipe: Option<impl std::io::Read + Send + 'static>) -> std::thread::JoinHandle<String> {
+    std::thread::spawn(move || {
+        let mut output = vec![];
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut output);
+        }
+        String::from_utf8_lossy(&output).into_owned()
+    })
+}
+
+fn parse_json(text: &str) -> ScriptResult<Dynamic> {
+    let value: serde_json::Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    rhai::serde::to_dynamic(value)
+}
@@ -6,10 +6,12 @@ use tokio::signal::unix::{signal, Signal, SignalKind};
 use super::{BlockStream, BlockStreamConfig};
 use crate::{config::RTSigNum, RENDERER};
 
-#[derive(serde::Serialize, Debug, Clone)]
-struct BlockData<'a> {
-    command: &'a str,
-    args: &'a Vec<String>,
+pub const DEFAULT_TEMPLATE: &str = "{{output}}";
+
+#[derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema, Debug, Clone)]
+pub struct BlockData {
+    command: String,
+    args: Vec<String>,
     signal: i32,
     status: i32,
     output: serde_json::Value,
@@ -51,7 +53,9 @@ impl Block {
 
 impl BlockStreamConfig for crate::config::SignalConfig {
     fn to_stream(self, name: String) -> Result<BlockStream> {
-        let template = self.template.unwrap_or_else(|| "{{output}}".to_string());
+        let template = self
+            .template
+            .unwrap_or_else(|| DEFAULT_TEMPLATE.to_string());
         RENDERER.add_template(&name, &template)?;
 
         let block = Block::new(
@@ -90,8 +94,8 @@ async fn render_output(
         serde_json::json!(String::from_utf8_lossy(&process_output.stdout).trim())
     };
     let data = BlockData {
-        command,
-        args,
+        command: command.to_string(),
+        args: args.clone(),
         signal,
         status,
         output,
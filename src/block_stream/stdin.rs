@@ -9,10 +9,12 @@ use tokio::io::{AsyncBufReadExt, BufReader};
 use super::{util::send_or_eprint, BlockStream, BlockStreamConfig};
 use crate::RENDERER;
 
+pub const DEFAULT_TEMPLATE: &str = "{{output}}";
+
 static READER: Lazy<StdinReader> = Lazy::new(StdinReader::spawn);
 
-#[derive(serde::Serialize, Debug, Clone)]
-struct BlockData {
+#[derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema, Debug, Clone)]
+pub struct BlockData {
     output: serde_json::Value,
 }
 
@@ -44,7 +46,9 @@ impl Block {
 
 impl BlockStreamConfig for crate::config::StdinConfig {
     fn to_stream(self, name: String) -> Result<BlockStream> {
-        let template = self.template.unwrap_or_else(|| "{{output}}".to_string());
+        let template = self
+            .template
+            .unwrap_or_else(|| DEFAULT_TEMPLATE.to_string());
         RENDERER.add_template(&name, &template)?;
         let rx = READER.subscribe();
         let json = self.json;
@@ -59,6 +63,11 @@ impl BlockStreamConfig for crate::config::StdinConfig {
     }
 }
 
+/// Receive every line read from stdin.
+pub fn subscribe() -> Receiver<Result<String>> {
+    READER.subscribe()
+}
+
 struct StdinReader {
     senders: Arc<Mutex<Vec<Sender<Result<String>>>>>,
 }
@@ -1,4 +1,5 @@
 use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
 use anyhow::Result;
 use futures::stream;
@@ -7,7 +8,15 @@ use sensors::{FeatureType, Sensors, SubfeatureType};
 use super::{BlockStream, BlockStreamConfig};
 use crate::RENDERER;
 
-#[derive(serde::Serialize, Clone)]
+pub const DEFAULT_TEMPLATE: &str =
+    "{{ chips | last | get(key=1) | first | get(attribute=\"value\")}}";
+
+#[derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema, Debug, Clone)]
+pub struct BlockData {
+    chips: BTreeMap<String, Vec<SensorData>>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema, Debug, Clone)]
 struct SensorData {
     label: String,
     value: f64,
@@ -17,10 +26,18 @@ struct SensorData {
 struct Block {
     name: String,
     interval: u64,
+    sys_root: Option<PathBuf>,
 }
 
 impl Block {
     fn read_sensors(&self) -> BTreeMap<String, Vec<SensorData>> {
+        match &self.sys_root {
+            Some(sys_root) => read_hwmon(sys_root),
+            None => Self::read_libsensors(),
+        }
+    }
+
+    fn read_libsensors() -> BTreeMap<String, Vec<SensorData>> {
         let sensors = Sensors::new();
 
         let mut chips: BTreeMap<String, Vec<SensorData>> = BTreeMap::new();
@@ -73,7 +90,7 @@ impl Block {
 
         let chips = self.read_sensors();
 
-        let rendered = match RENDERER.render(&self.name, serde_json::json!({ "chips": chips })) {
+        let rendered = match RENDERER.render(&self.name, BlockData { chips }) {
             Ok(r) => r,
             Err(e) => return Some(Err(e)),
         };
@@ -83,14 +100,15 @@ impl Block {
 
 impl BlockStreamConfig for crate::config::TemperatureConfig {
     fn to_stream(self, name: String) -> Result<BlockStream> {
-        let template = self.template.unwrap_or_else(|| {
-            "{{ chips | last | get(key=1) | first | get(attribute=\"value\")}}".to_string()
-        });
+        let template = self
+            .template
+            .unwrap_or_else(|| DEFAULT_TEMPLATE.to_string());
         RENDERER.add_template(&name, &template)?;
 
         let block = Block {
             name,
             interval: self.interval,
+            sys_root: self.sys_root,
         };
         let stream = stream::unfold(block, move |mut block| async {
             let result = block.wait_for_output().await?;
@@ -100,3 +118,58 @@ impl BlockStreamConfig for crate::config::TemperatureConfig {
         Ok(Box::pin(stream))
     }
 }
+
+/// Read temperatures straight from the hwmon devices under `sys_root`, without libsensors.
+///
+/// Chips are named `<name>-<device>` (e.g. `coretemp-hwmon1`).
+fn read_hwmon(sys_root: &Path) -> BTreeMap<String, Vec<SensorData>> {
+    let mut chips = BTreeMap::new();
+    let mut devices: Vec<_> = match std::fs::read_dir(sys_root.join("class/hwmon")) {
+        Ok(entries) => entries
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .collect(),
+        Err(_) => return chips,
+    };
+    devices.sort();
+
+    for device in devices {
+        let read =
+            |file: &str| std::fs::read_to_string(device.join(file)).map(|s| s.trim().to_string());
+        let chip_prefix = match read("name") {
+            Ok(name) => name,
+            Err(_) => continue,
+        };
+        let mut indices: Vec<u32> = match std::fs::read_dir(&device) {
+            Ok(entries) => entries
+                .filter_map(|entry| {
+                    let file_name = entry.ok()?.file_name();
+                    let index = file_name.to_str()?.strip_prefix("temp")?;
+                    index.strip_suffix("_input")?.parse().ok()
+                })
+                .collect(),
+            Err(_) => continue,
+        };
+        indices.sort_unstable();
+
+        let mut chip_sensors = Vec::new();
+        for index in indices {
+            let value = match read(&format!("temp{index}_input")).map(|s| s.parse::<f64>()) {
+                Ok(Ok(millidegrees)) => millidegrees / 1000.0,
+                _ => continue,
+            };
+            let label = read(&format!("temp{index}_label")).unwrap_or(format!("temp{index}"));
+            chip_sensors.push(SensorData {
+                label,
+                value,
+                chip: chip_prefix.clone(),
+            });
+        }
+
+        if !chip_sensors.is_empty() {
+            let device_name = device.file_name().unwrap_or_default().to_string_lossy();
+            chips.insert(format!("{chip_prefix}-{device_name}"), chip_sensors);
+        }
+    }
+
+    chips
+}
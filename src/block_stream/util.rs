@@ -1,7 +1,24 @@
 use std::fmt::Debug;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use futures::channel::mpsc::Sender;
 
+/// Time until the next multiple of `interval` seconds since the epoch, or `None` if `interval`
+/// is 0.
+pub fn next_boundary(interval: u64) -> Option<Duration> {
+    if interval == 0 {
+        return None;
+    }
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let elapsed = Duration::new(now.as_secs() % interval, now.subsec_nanos());
+    if elapsed.is_zero() {
+        return Some(Duration::ZERO);
+    }
+    Some(Duration::from_secs(interval) - elapsed)
+}
+
 /// Send a message down the channel or print to stderr if the channel is disconnected.
 pub fn send_or_eprint<T: Debug>(mut message: T, tx: &mut Sender<T>) {
     while let Err(error) = tx.try_send(message) {
@@ -16,3 +33,17 @@ pub fn send_or_eprint<T: Debug>(mut message: T, tx: &mut Sender<T>) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn next_boundary_within_interval() {
+        assert_eq!(next_boundary(0), None);
+        assert!(next_boundary(60).unwrap() <= Duration::from_secs(60));
+        // Long enough to overflow a count of nanoseconds.
+        let remaining = next_boundary(u64::MAX).unwrap();
+        assert!(remaining > Duration::from_secs(u64::MAX / 2));
+    }
+}
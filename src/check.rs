@@ -0,0 +1,138 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+use crate::config::{self, BlockConfig, Config, ConfigError, Location};
+use crate::{block_stream, theme, RENDERER};
+
+/// Check the config for problems without running any blocks, reporting every problem found.
+pub fn run(file: Option<PathBuf>) -> Result<()> {
+    let (config, mut problems) = config::read_config(file)?;
+    let source = std::fs::read_to_string(&config.file)?;
+    let config_error = |key: &str, error: anyhow::Error| ConfigError {
+        location: Location::new(&config.file, config::locate_key(&source, key)),
+        block: None,
+        message: format!("{error:#}"),
+    };
+
+    if let Err(error) = RENDERER.set_vars(&config.vars) {
+        problems.push(config_error("vars", error));
+    }
+    if let Err(error) = theme::build_palette(config.theme_file.as_deref(), &config.palette) {
+        problems.push(config_error("theme-file", error));
+    }
+    if let Some(escape) = config.autoescape {
+        RENDERER.set_autoescape(escape);
+    }
+    if let Some(dir) = &config.templates_dir {
+        if let Err(error) = RENDERER.load_templates_dir(dir) {
+            problems.push(config_error("templates-dir", error));
+        }
+    }
+    if let Some(handler) = &config.stdin_handler {
+        if let Err(error) = check_command(&handler.command) {
+            problems.push(config_error("stdin-handler", error));
+        }
+    }
+    for (name, block) in &config.blocks {
+        for error in check_block(name, block, config.stdin_handler.is_some()) {
+            problems.push(ConfigError {
+                location: block.location.clone(),
+                block: Some(name.clone()),
+                message: format!("{error:#}"),
+            });
+        }
+    }
+    // Check the root template last, so it can use block templates.
+    if let Err(error) = RENDERER.set_root_template(&config) {
+        let key = match config.template_file {
+            Some(_) => "template-file",
+            None => "template",
+        };
+        problems.push(config_error(key, error));
+    }
+
+    report(&config, problems)
+}
+
+fn report(config: &Config, problems: Vec<ConfigError>) -> Result<()> {
+    for problem in &problems {
+        eprintln!("{problem}");
+    }
+    match problems.len() {
+        0 => {
+            println!("{}: OK", config.file.display());
+            Ok(())
+        }
+        1 => anyhow::bail!("Found 1 problem"),
+        count => anyhow::bail!("Found {count} problems"),
+    }
+}
+
+/// Compile a block's templates, and check that any devices, files or commands it uses exist.
+fn check_block(name: &str, block: &config::Block, stdin_handler: bool) -> Vec<anyhow::Error> {
+    let mut errors = vec![];
+    errors.extend(block_stream::add_template(name, block).err());
+
+    let resource = match &block.config {
+        BlockConfig::Command(config) => check_command(&config.command),
+        BlockConfig::Interval(config) => config
+            .validate()
+            .and_then(|_| check_command(&config.command)),
+        BlockConfig::Signal(config) => check_command(&config.command),
+        BlockConfig::Plugin(config) => check_command(&config.command),
+        BlockConfig::Script(config) => block_stream::check_script(config),
+        BlockConfig::Inotify(config) => check_watch_dir(&config.file),
+        BlockConfig::Network(config) => check_device(config.sys_root.as_deref(), &config.device),
+        BlockConfig::NetworkStats(config) => {
+            check_device(config.sys_root.as_deref(), &config.device)
+        }
+        _ => Ok(()),
+    };
+    if stdin_handler && block.config.reads_stdin() {
+        errors.push(anyhow::anyhow!(
+            "Cannot use stdin block or plugin clicks with stdin_handler"
+        ));
+    }
+    errors.extend(resource.err());
+
+    errors
+}
+
+/// Check that `command` is an executable file, or can be found on `PATH`.
+fn check_command(command: &str) -> Result<()> {
+    let is_executable = |path: &Path| {
+        use std::os::unix::fs::PermissionsExt;
+        path.metadata()
+            .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+    };
+    let found = if command.contains('/') {
+        is_executable(Path::new(command))
+    } else {
+        std::env::var_os("PATH").is_some_and(|paths| {
+            std::env::split_paths(&paths).any(|dir| is_executable(&dir.join(command)))
+        })
+    };
+    anyhow::ensure!(found, "Command `{command}` not found");
+
+    Ok(())
+}
+
+/// Check that the directory containing `file` exists, so it can be watched.
+fn check_watch_dir(file: &Path) -> Result<()> {
+    let dir = file.parent().unwrap_or_else(|| Path::new("/"));
+    anyhow::ensure!(dir.is_dir(), "Directory {} not found", dir.display());
+
+    Ok(())
+}
+
+/// Check that the network device `device` exists.
+fn check_device(sys_root: Option<&Path>, device: &str) -> Result<()> {
+    let sys_root = sys_root.unwrap_or_else(|| Path::new(config::DEFAULT_SYS_ROOT));
+    anyhow::ensure!(
+        sys_root.join("class/net").join(device).exists(),
+        "Network device `{device}` not found"
+    );
+
+    Ok(())
+}
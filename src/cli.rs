@@ -0,0 +1,150 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use clap::{AppSettings, Parser};
+
+use crate::block_stream::BlockStreamConfig;
+use crate::{
+    check, config, describe, fixtures, preview, recording, run_blocks, run_once, stdin_handler,
+    RENDERER,
+};
+
+/// Run yablocks with the given command line arguments.
+pub async fn run(args: Args) -> Result<()> {
+    match args.command {
+        Some(Command::Check) => return check::run(args.configfile),
+        Some(Command::Describe { kind, schema }) => return describe::run(kind, schema),
+        Some(Command::Preview { block }) => return preview::run(args.configfile, block).await,
+        Some(Command::Test { fixtures, update }) => {
+            return fixtures::run(args.configfile, fixtures, update)
+        }
+        Some(Command::Replay { recording, speed }) => {
+            return recording::replay(args.configfile, recording, speed).await
+        }
+        None => {}
+    }
+    let config = config::load_config(args.configfile).context("Failed to load config")?;
+
+    // If an stdin_handler is specified, make sure no blocks read stdin, then run it.
+    if let Some(handler) = &config.stdin_handler {
+        if config
+            .blocks
+            .iter()
+            .any(|(_, block)| block.config.reads_stdin())
+        {
+            anyhow::bail!("Cannot use stdin block or plugin clicks with stdin_handler");
+        }
+        // There's nothing to handle clicks on when rendering once.
+        if !args.once {
+            stdin_handler::spawn_handler(handler.clone())?;
+        }
+    }
+    for name in &args.block {
+        if !config
+            .blocks
+            .iter()
+            .any(|(block_name, _)| block_name == name)
+        {
+            anyhow::bail!("No block named '{name}'");
+        }
+    }
+
+    RENDERER.configure(&config)?;
+    RENDERER
+        .set_root_template(&config)
+        .context("Failed to build template renderer")?;
+    if let Some(file) = &args.record {
+        let recorder = recording::Recorder::create(file)
+            .with_context(|| format!("Failed to create recording {}", file.display()))?;
+        RENDERER.set_recorder(recorder);
+    }
+
+    let (names, block_streams): (Vec<_>, Vec<_>) = config
+        .blocks
+        .clone()
+        .into_iter()
+        .filter(|(name, _)| args.block.is_empty() || args.block.contains(name))
+        .map(|(name, config)| {
+            let block_stream = config
+                .to_stream(name.clone())
+                .with_context(|| format!("Failed to initialize block '{name}'"));
+            (name, block_stream)
+        })
+        .filter_map(|(name, result)| match result {
+            Ok(block_stream) => Some((name, block_stream)),
+            Err(error) => {
+                eprintln!("{error:?}");
+                None
+            }
+        })
+        .unzip();
+
+    if args.once {
+        anyhow::ensure!(
+            args.timeout.is_finite() && args.timeout >= 0.0,
+            "Timeout must be a non-negative number of seconds"
+        );
+        let timeout = std::time::Duration::from_secs_f64(args.timeout);
+        run_once(config, names, block_streams, timeout).await?;
+        // Some blocks run on threads which never finish, so exit without waiting for them.
+        std::process::exit(0);
+    }
+    run_blocks(config, block_streams).await
+}
+
+#[derive(Parser, Debug, Clone)]
+#[clap(version, setting=AppSettings::DeriveDisplayOrder)]
+pub struct Args {
+    #[clap(short, long, global = true)]
+    pub configfile: Option<PathBuf>,
+    /// Record the data from every block to this file
+    #[clap(long, value_name = "FILE")]
+    pub record: Option<PathBuf>,
+    /// Print the output once, as soon as every block has produced a value, then exit
+    #[clap(long)]
+    pub once: bool,
+    /// Only run this block (can be repeated)
+    #[clap(long, value_name = "NAME")]
+    pub block: Vec<String>,
+    /// Seconds to wait for blocks when using --once
+    #[clap(long, value_name = "SECONDS", default_value = "5", requires = "once")]
+    pub timeout: f64,
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(clap::Subcommand, Debug, Clone)]
+pub enum Command {
+    /// Check the config for problems without running any blocks
+    Check,
+    /// Describe the inputs and outputs of each block kind
+    Describe {
+        /// Only describe this block kind
+        kind: Option<String>,
+        /// Print a JSON Schema for the config file instead
+        #[clap(long)]
+        schema: bool,
+    },
+    /// Run a single block, printing its data and output on every update
+    Preview {
+        /// The name of the block to preview
+        block: String,
+    },
+    /// Render templates from fixture data and compare the output to snapshots
+    Test {
+        /// The fixtures directory [default: `fixtures` next to the config file]
+        #[clap(long)]
+        fixtures: Option<PathBuf>,
+        /// Write the current output to the snapshots instead of comparing
+        #[clap(long)]
+        update: bool,
+    },
+    /// Replay a recording made with `--record` instead of running any blocks
+    Replay {
+        /// The recording to replay
+        recording: PathBuf,
+        /// Replay this many times faster than the original
+        #[clap(long, default_value = "1")]
+        speed: f64,
+    },
+}
@@ -1,38 +1,585 @@
 use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-pub fn load_config(file: Option<std::path::PathBuf>) -> Result<Config> {
+use crate::block_stream;
+
+mod expand;
+
+pub fn load_config(file: Option<PathBuf>) -> Result<Config> {
+    let (config, errors) = read_config(file)?;
+    if let Some(error) = errors.into_iter().next() {
+        return Err(error.into());
+    }
+
+    Ok(config)
+}
+
+/// Load the config, collecting any problems with included files or individual blocks instead
+/// of failing on the first one.
+///
+/// Blocks with problems are left out of the returned config.
+pub fn read_config(file: Option<PathBuf>) -> Result<(Config, Vec<ConfigError>)> {
     let file = match file {
         Some(file) => file,
-        None => xdg::BaseDirectories::with_prefix(env!("CARGO_BIN_NAME"))?
+        None => xdg::BaseDirectories::with_prefix(env!("CARGO_PKG_NAME"))?
             .find_config_file("config.toml")
             .ok_or_else(|| anyhow::anyhow!("Failed to find config"))?,
     };
 
-    let config: Config = toml::from_str(&std::fs::read_to_string(file)?)?;
-    Ok(config)
+    let source = std::fs::read_to_string(&file)
+        .with_context(|| format!("Failed to read {}", file.display()))?;
+    let mut config: Config =
+        toml::from_str(&source).map_err(|error| ConfigError::from_toml(&file, &source, error))?;
+    config.file = file.clone();
+    let mut errors = vec![];
+    let base_dir = file.parent().unwrap_or_else(|| Path::new("."));
+    let mut sources = vec![(file.clone(), source, config.raw_blocks.take())];
+    for fragment_file in include_files(&config.include, base_dir)? {
+        let source = std::fs::read_to_string(&fragment_file)
+            .with_context(|| format!("Failed to read {}", fragment_file.display()))?;
+        match toml::from_str::<Fragment>(&source) {
+            Ok(fragment) => sources.push((fragment_file, source, fragment.blocks)),
+            Err(error) => errors.push(ConfigError::from_toml(&fragment_file, &source, error)),
+        }
+    }
+
+    let mut origins: BTreeMap<String, PathBuf> = BTreeMap::new();
+    for (source_file, source, raw_blocks) in sources {
+        let Some(raw_blocks) = raw_blocks else {
+            continue;
+        };
+        for result in parse_blocks(raw_blocks, &source_file, &source) {
+            let (name, block) = match result {
+                Ok((name, block)) if block.is_enabled() => (name, block),
+                Ok(_) => continue,
+                Err(error) => {
+                    errors.push(error);
+                    continue;
+                }
+            };
+            if RESERVED_BLOCK_NAMES.contains(&name.as_str()) {
+                errors.push(ConfigError {
+                    location: block.location.clone(),
+                    block: Some(name),
+                    message: "block name is reserved for the root template".to_string(),
+                });
+                continue;
+            }
+            if let Some(origin) = origins.get(&name) {
+                errors.push(ConfigError {
+                    location: block.location.clone(),
+                    block: Some(name),
+                    message: format!("already defined in {}", origin.display()),
+                });
+                continue;
+            }
+            origins.insert(name.clone(), source_file.clone());
+            config.blocks.push((name, block));
+        }
+    }
+    for (_, block) in &mut config.blocks {
+        block
+            .config
+            .inherit_roots(config.proc_root.as_deref(), config.sys_root.as_deref());
+    }
+
+    Ok((config, errors))
+}
+
+/// Names the root template uses for its own values, which blocks can't be called.
+const RESERVED_BLOCK_NAMES: [&str; 4] = ["blocks", "states", "vars", "palette"];
+
+/// Parse a block, which can be of a kind added with [`register_block`](crate::register_block).
+fn parse_block(value: toml::Value) -> std::result::Result<Block, toml::de::Error> {
+    let kind = match value.get("kind").and_then(toml::Value::as_str) {
+        Some(kind) if block_stream::is_custom_kind(kind) => kind.to_string(),
+        _ => return value.try_into(),
+    };
+    let mut custom_block: CustomBlock = value.try_into()?;
+    custom_block.config.remove("kind");
+    let config = toml::Value::Table(custom_block.config);
+    block_stream::check_custom_config(&kind, config.clone())?;
+
+    Ok(Block {
+        options: custom_block.options,
+        config: BlockConfig::Custom(CustomConfig { kind, config }),
+        location: Location::default(),
+    })
+}
+
+/// Parse the raw `blocks` value from a config file.
+///
+/// A table of blocks is sorted by name, and an array of blocks keeps its order.
+fn parse_blocks(
+    raw_blocks: toml::Value,
+    file: &Path,
+    source: &str,
+) -> Vec<std::result::Result<(String, Block), ConfigError>> {
+    let entries: Vec<_> = match raw_blocks {
+        toml::Value::Table(table) => {
+            let offsets = table_offsets(source);
+            table
+                .into_iter()
+                .map(|(name, value)| {
+                    let position = offsets.get(&name).map(|&offset| position(source, offset));
+                    (Ok(name), value, Location::new(file, position))
+                })
+                .collect()
+        }
+        toml::Value::Array(array) => {
+            let offsets = array_offsets(source);
+            array
+                .into_iter()
+                .enumerate()
+                .map(|(index, mut value)| {
+                    let offset = offsets.get(index).copied().flatten();
+                    let location =
+                        Location::new(file, offset.map(|offset| position(source, offset)));
+                    let name = match value.as_table_mut().and_then(|table| table.remove("name")) {
+                        Some(toml::Value::String(name)) => Ok(name),
+                        Some(_) => Err("expected `name` to be a string"),
+                        None => Err("missing field `name`"),
+                    };
+                    (name, value, location)
+                })
+                .collect()
+        }
+        _ => {
+            return vec![Err(ConfigError {
+                location: Location::new(file, locate_key(source, "blocks")),
+                block: None,
+                message: "expected a table or an array of blocks".to_string(),
+            })]
+        }
+    };
+
+    entries
+        .into_iter()
+        .map(|(name, value, location)| {
+            let name = name.map_err(|message| ConfigError {
+                location: location.clone(),
+                block: None,
+                message: message.to_string(),
+            })?;
+            match parse_block(value) {
+                Ok(block) => Ok((name, Block { location, ..block })),
+                Err(error) => {
+                    let message = error.to_string();
+                    let position = refine_position(source, location.position, &message);
+                    Err(ConfigError {
+                        location: Location::new(file, position),
+                        block: Some(name),
+                        message,
+                    })
+                }
+            }
+        })
+        .collect()
+}
+
+/// Byte offsets of the block names in a table of blocks, however each block is written.
+fn table_offsets(source: &str) -> BTreeMap<String, usize> {
+    #[derive(Deserialize)]
+    struct Spans {
+        blocks: BTreeMap<toml::Spanned<String>, serde::de::IgnoredAny>,
+    }
+
+    toml::from_str::<Spans>(source)
+        .map(|spans| {
+            spans
+                .blocks
+                .into_keys()
+                .map(|name| (name.get_ref().clone(), name.start()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Byte offsets of each entry in an array of blocks.
+///
+/// `[[blocks]]` entries have no span of their own, so they're located by their first key.
+fn array_offsets(source: &str) -> Vec<Option<usize>> {
+    type Entry = BTreeMap<toml::Spanned<String>, serde::de::IgnoredAny>;
+    #[derive(Deserialize)]
+    struct Spans {
+        blocks: Vec<toml::Spanned<Entry>>,
+    }
+
+    toml::from_str::<Spans>(source)
+        .map(|spans| {
+            spans
+                .blocks
+                .into_iter()
+                .map(|entry| {
+                    if entry.start() < entry.end() {
+                        Some(entry.start())
+                    } else {
+                        entry.get_ref().keys().map(toml::Spanned::start).min()
+                    }
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The 1-based line and column of a byte offset in `source`.
+fn position(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset];
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+    (before.matches('\n').count() + 1, offset - line_start + 1)
+}
+
+/// Find where the top level `key` is set in `source`.
+pub fn locate_key(source: &str, key: &str) -> Option<(usize, usize)> {
+    locate_line(source, |line| {
+        line.strip_prefix('[')
+            .and_then(|line| line.strip_suffix(']'))
+            .is_some_and(|header| header.trim() == key)
+            || line
+                .strip_prefix(key)
+                .is_some_and(|rest| rest.trim_start().starts_with('='))
+    })
+}
+
+/// Point errors about unknown fields at the field itself rather than the start of its table.
+fn refine_position(
+    source: &str,
+    position: Option<(usize, usize)>,
+    message: &str,
+) -> Option<(usize, usize)> {
+    let field = message
+        .strip_prefix("unknown field `")
+        .and_then(|rest| rest.split_once('`'))
+        .map(|(field, _)| field);
+    let (Some(field), Some((line, _))) = (field, position) else {
+        return position;
+    };
+    let skipped: usize = source.lines().take(line - 1).map(|l| l.len() + 1).sum();
+    match locate_key(source.get(skipped..)?, field) {
+        Some((field_line, column)) => Some((line + field_line - 1, column)),
+        None => position,
+    }
+}
+
+/// Find the first line (ignoring surrounding whitespace) matching `predicate`, returning its
+/// 1-based line and column.
+fn locate_line(source: &str, mut predicate: impl FnMut(&str) -> bool) -> Option<(usize, usize)> {
+    source.lines().enumerate().find_map(|(index, line)| {
+        let trimmed = line.trim();
+        predicate(trimmed).then(|| (index + 1, line.len() - line.trim_start().len() + 1))
+    })
+}
+
+/// Where something is defined in the config.
+#[derive(Debug, Clone, Default)]
+pub struct Location {
+    pub file: PathBuf,
+    /// 1-based line and column, if known.
+    pub position: Option<(usize, usize)>,
+}
+
+impl Location {
+    pub fn new(file: &Path, position: Option<(usize, usize)>) -> Self {
+        Self {
+            file: file.to_path_buf(),
+            position,
+        }
+    }
+}
+
+impl std::fmt::Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.position {
+            Some((line, column)) => write!(f, "{}:{line}:{column}", self.file.display()),
+            None => write!(f, "{}", self.file.display()),
+        }
+    }
+}
+
+/// A problem with the config, along with where it was found.
+#[derive(Debug, Clone)]
+pub struct ConfigError {
+    pub location: Location,
+    pub block: Option<String>,
+    pub message: String,
+}
+
+impl ConfigError {
+    fn from_toml(file: &Path, source: &str, error: toml::de::Error) -> Self {
+        let position = error
+            .line_col()
+            .map(|(line, column)| (line + 1, column + 1));
+        let message = error.to_string();
+        let message = match (position, message.rsplit_once(" at line ")) {
+            (Some(_), Some((message, _))) => message.to_string(),
+            _ => message,
+        };
+        Self {
+            location: Location::new(file, refine_position(source, position, &message)),
+            block: None,
+            message,
+        }
+    }
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match &self.block {
+            Some(block) => write!(f, "{}: block '{block}': {}", self.location, self.message),
+            None => write!(f, "{}: {}", self.location, self.message),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Expand `include` patterns into a list of files.
+///
+/// Patterns are relative to the directory containing the config file and are expanded in
+/// order. The files matching each pattern are sorted by path. Paths without glob characters
+/// must exist, so a typo doesn't silently drop blocks.
+fn include_files(includes: &[Include], base_dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    for include in includes {
+        let pattern = match include {
+            Include::Pattern(pattern) => pattern,
+            Include::Table(table) if table.enabled_if.is_met() => &table.path,
+            Include::Table(_) => continue,
+        };
+        let pattern = expand::expand(pattern);
+        if !pattern.contains(['*', '?', '[']) {
+            let file = base_dir.join(pattern);
+            if !file.exists() {
+                anyhow::bail!("Included file {} not found", file.display());
+            }
+            files.push(file);
+            continue;
+        }
+        let pattern = base_dir.join(pattern);
+        let mut matches = glob::glob(&pattern.to_string_lossy())
+            .with_context(|| format!("Invalid include pattern {}", pattern.display()))?
+            .collect::<Result<Vec<_>, _>>()?;
+        matches.sort();
+        files.extend(matches);
+    }
+
+    Ok(files)
+}
+
+#[derive(Deserialize, JsonSchema, Debug, Clone)]
+#[serde(untagged)]
+pub enum Include {
+    Pattern(String),
+    Table(IncludeTable),
+}
+
+#[derive(Deserialize, JsonSchema, Debug, Clone)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct IncludeTable {
+    pub path: String,
+    pub enabled_if: Condition,
+}
+
+/// Conditions for enabling a block or include. All the given conditions must be met.
+#[derive(Deserialize, JsonSchema, Debug, Clone, Default)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Condition {
+    /// The machine's hostname.
+    pub hostname: Option<String>,
+    /// A path which must exist.
+    #[serde(default, deserialize_with = "expand::optional_path")]
+    pub path_exists: Option<PathBuf>,
+    /// An environment variable which must be set (`VAR`) or have a given value (`VAR=value`).
+    pub env: Option<String>,
 }
 
+impl Condition {
+    pub fn is_met(&self) -> bool {
+        if let Some(hostname) = &self.hostname {
+            let actual = std::fs::read_to_string("/proc/sys/kernel/hostname").unwrap_or_default();
+            if actual.trim() != hostname {
+                return false;
+            }
+        }
+        if let Some(path) = &self.path_exists {
+            if !path.exists() {
+                return false;
+            }
+        }
+        if let Some(env) = &self.env {
+            let met = match env.split_once('=') {
+                Some((var, value)) => std::env::var(var).is_ok_and(|v| v == value),
+                None => std::env::var_os(env).is_some_and(|v| !v.is_empty()),
+            };
+            if !met {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// A config file included from the main config.
 #[derive(Deserialize, Debug, Clone)]
 #[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct Fragment {
+    blocks: Option<toml::Value>,
+}
+
+#[derive(Deserialize, JsonSchema, Debug, Clone)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
 pub struct Config {
-    pub template: String,
+    pub template: Option<String>,
+    #[serde(default, deserialize_with = "expand::optional_path")]
+    pub template_file: Option<std::path::PathBuf>,
+    #[serde(default, deserialize_with = "expand::optional_path")]
+    pub templates_dir: Option<std::path::PathBuf>,
     pub header: Option<String>,
     pub stdin_handler: Option<StdinHandler>,
+    pub autoescape: Option<Escape>,
     #[serde(default)]
-    pub blocks: BTreeMap<String, BlockConfig>,
+    #[schemars(with = "BTreeMap<String, serde_json::Value>")]
+    pub vars: BTreeMap<String, toml::Value>,
+    #[serde(default)]
+    pub palette: BTreeMap<String, String>,
+    #[serde(default, deserialize_with = "expand::optional_path")]
+    pub theme_file: Option<std::path::PathBuf>,
+    #[serde(default)]
+    pub include: Vec<Include>,
+    #[serde(default = "default_separator")]
+    pub separator: String,
+    #[serde(default, deserialize_with = "expand::optional_path")]
+    pub proc_root: Option<PathBuf>,
+    #[serde(default, deserialize_with = "expand::optional_path")]
+    pub sys_root: Option<PathBuf>,
+    #[serde(rename = "blocks")]
+    #[schemars(with = "Option<serde_json::Value>")]
+    raw_blocks: Option<toml::Value>,
+    #[serde(skip)]
+    pub blocks: Vec<(String, Block)>,
+    /// The file the config was loaded from.
+    #[serde(skip)]
+    pub file: PathBuf,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+fn default_separator() -> String {
+    " | ".to_string()
+}
+
+/// Markup dialects that untrusted block output can be escaped for.
+#[derive(Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Escape {
+    Lemonbar,
+    Dzen,
+    Xmobar,
+    Pango,
+    Json,
+}
+
+impl Escape {
+    pub const ALL: [Escape; 5] = [
+        Escape::Lemonbar,
+        Escape::Dzen,
+        Escape::Xmobar,
+        Escape::Pango,
+        Escape::Json,
+    ];
+}
+
+#[derive(Deserialize, JsonSchema, Debug, Clone)]
 #[serde(rename_all = "kebab-case", deny_unknown_fields)]
 pub struct StdinHandler {
+    #[serde(deserialize_with = "expand::string")]
     pub command: String,
-    #[serde(default)]
+    #[serde(default, deserialize_with = "expand::strings")]
     pub args: Vec<String>,
 }
 
+/// A block's kind-specific config, along with options common to all blocks.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Block {
+    #[serde(flatten)]
+    pub options: BlockOptions,
+    #[serde(flatten)]
+    pub config: BlockConfig,
+    #[serde(skip)]
+    pub location: Location,
+}
+
+impl Block {
+    fn is_enabled(&self) -> bool {
+        self.options
+            .enabled_if
+            .as_ref()
+            .is_none_or(Condition::is_met)
+    }
+}
+
+#[derive(Deserialize, JsonSchema, Debug, Clone, Default)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct BlockOptions {
+    /// Only run the block if these conditions are met.
+    pub enabled_if: Option<Condition>,
+    /// File to load the block's template from, instead of `template`.
+    #[serde(default, deserialize_with = "expand::optional_path")]
+    pub template_file: Option<std::path::PathBuf>,
+    /// Number of recent outputs to keep and expose to the template as `history`.
+    #[serde(default)]
+    pub history: usize,
+    /// Scroll output wider than a maximum width.
+    pub marquee: Option<MarqueeConfig>,
+    /// Classify the block's data into a `state`.
+    pub thresholds: Option<ThresholdsConfig>,
+}
+
+const fn default_marquee_interval() -> f64 {
+    0.5
+}
+
+fn default_marquee_separator() -> String {
+    " | ".to_string()
+}
+
+#[derive(Deserialize, JsonSchema, Debug, Clone)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct MarqueeConfig {
+    pub width: usize,
+    #[serde(default = "default_marquee_interval")]
+    pub interval: f64,
+    #[serde(default = "default_marquee_separator")]
+    pub separator: String,
+}
+
+#[derive(
+    Deserialize, Serialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum State {
+    Idle,
+    Info,
+    Good,
+    Warning,
+    Critical,
+}
+
+#[derive(Deserialize, JsonSchema, Debug, Clone)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ThresholdsConfig {
+    /// Dotted path to the numeric field to classify (e.g. `cpu_times.non_idle`).
+    pub field: String,
+    pub info: Option<f64>,
+    pub good: Option<f64>,
+    pub warning: Option<f64>,
+    pub critical: Option<f64>,
+    #[serde(default)]
+    pub colors: BTreeMap<State, String>,
+}
+
 #[derive(Deserialize, Debug, Clone)]
 #[serde(tag = "kind", rename_all = "kebab-case")]
 pub enum BlockConfig {
@@ -44,84 +591,284 @@ pub enum BlockConfig {
     Network(NetworkConfig),
     NetworkStats(NetworkStatsConfig),
     Noop(NoopConfig),
+    Plugin(PluginConfig),
     PulseVolume(PulseVolumeConfig),
+    Script(ScriptConfig),
     Signal(SignalConfig),
     Stdin(StdinConfig),
     Temperature(TemperatureConfig),
+    /// A block kind added with [`register_block`](crate::register_block).
+    #[serde(skip)]
+    Custom(CustomConfig),
 }
 
-#[derive(Deserialize, Debug, Clone)]
+impl BlockConfig {
+    /// The template configured for the block, if any.
+    pub fn template(&self) -> Option<&str> {
+        match self {
+            BlockConfig::Command(config) => config.template.as_deref(),
+            BlockConfig::Cpu(config) => config.template.as_deref(),
+            BlockConfig::DateTime(config) => config.template.as_deref(),
+            BlockConfig::Interval(config) => config.template.as_deref(),
+            BlockConfig::Inotify(config) => config.template.as_deref(),
+            BlockConfig::Network(config) => config.template.as_deref(),
+            BlockConfig::NetworkStats(config) => config.template.as_deref(),
+            BlockConfig::Noop(config) => config.template.as_deref(),
+            BlockConfig::Plugin(config) => config.template.as_deref(),
+            BlockConfig::PulseVolume(config) => config.template.as_deref(),
+            BlockConfig::Script(config) => config.template.as_deref(),
+            BlockConfig::Signal(config) => config.template.as_deref(),
+            BlockConfig::Stdin(config) => config.template.as_deref(),
+            BlockConfig::Temperature(config) => config.template.as_deref(),
+            BlockConfig::Custom(config) => config.config.get("template")?.as_str(),
+        }
+    }
+
+    /// Whether the block reads from stdin, which can't be shared with a `stdin-handler`.
+    pub fn reads_stdin(&self) -> bool {
+        match self {
+            BlockConfig::Stdin(_) => true,
+            BlockConfig::Plugin(config) => config.clicks,
+            _ => false,
+        }
+    }
+
+    /// Use the global `proc-root` and `sys-root` for blocks which don't set their own.
+    ///
+    /// Temperature blocks are left alone: with a `sys-root` they read hwmon directly instead of
+    /// using libsensors, which changes their chip names.
+    fn inherit_roots(&mut self, proc_root: Option<&Path>, sys_root: Option<&Path>) {
+        fn inherit(root: &mut Option<PathBuf>, global: Option<&Path>) {
+            if root.is_none() {
+                *root = global.map(Path::to_path_buf);
+            }
+        }
+        match self {
+            BlockConfig::Cpu(config) => inherit(&mut config.proc_root, proc_root),
+            BlockConfig::Network(config) => {
+                inherit(&mut config.proc_root, proc_root);
+                inherit(&mut config.sys_root, sys_root);
+            }
+            BlockConfig::NetworkStats(config) => inherit(&mut config.sys_root, sys_root),
+            _ => {}
+        }
+    }
+}
+
+/// The config of a block of a registered kind, checked but kept as toml until it starts.
+#[derive(Debug, Clone)]
+pub struct CustomConfig {
+    pub kind: String,
+    pub config: toml::Value,
+}
+
+/// A block of a registered kind, with the common options separated from its own config.
+#[derive(Deserialize)]
+struct CustomBlock {
+    #[serde(flatten)]
+    options: BlockOptions,
+    #[serde(flatten)]
+    config: toml::value::Table,
+}
+
+#[derive(Deserialize, JsonSchema, Debug, Clone)]
 #[serde(rename_all = "kebab-case", deny_unknown_fields)]
 pub struct CommandConfig {
     pub template: Option<String>,
+    #[serde(deserialize_with = "expand::string")]
     pub command: String,
-    #[serde(default)]
+    #[serde(default, deserialize_with = "expand::strings")]
     pub args: Vec<String>,
     #[serde(default)]
     pub json: bool,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, JsonSchema, Debug, Clone)]
 #[serde(rename_all = "kebab-case", deny_unknown_fields)]
 pub struct IntervalConfig {
     pub template: Option<String>,
+    #[serde(deserialize_with = "expand::string")]
     pub command: String,
-    #[serde(default)]
+    #[serde(default, deserialize_with = "expand::strings")]
     pub args: Vec<String>,
-    pub interval: u64,
+    /// Run the command this often, in seconds.
+    pub interval: Option<u64>,
+    /// Run the command at the times matched by a cron expression, instead of `interval`.
+    pub schedule: Option<Schedule>,
+    /// Run on multiples of `interval` since the epoch, so runs line up with the clock.
+    #[serde(default)]
+    pub align: bool,
     #[serde(default)]
     pub json: bool,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+impl IntervalConfig {
+    /// Check that exactly one of `interval` and `schedule` is set, and `align` makes sense.
+    pub fn validate(&self) -> Result<()> {
+        match (self.interval, &self.schedule) {
+            (None, None) => anyhow::bail!("One of interval or schedule is required"),
+            (Some(_), Some(_)) => anyhow::bail!("Cannot set both interval and schedule"),
+            (Some(0), None) if self.align => anyhow::bail!("Cannot align an interval of 0"),
+            (None, Some(_)) if self.align => anyhow::bail!("align can only be used with interval"),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// A cron expression. The leading seconds field is optional.
+///
+/// Days of the week are numbered like crontab, from Sunday as 0 (or 7) to Saturday as 6.
+#[derive(Deserialize, JsonSchema, Debug, Clone)]
+#[serde(try_from = "String")]
+#[schemars(with = "String")]
+pub struct Schedule(pub Box<cron::Schedule>);
+
+impl TryFrom<String> for Schedule {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let mut fields: Vec<String> = value.split_whitespace().map(String::from).collect();
+        if fields.len() == 5 {
+            fields.insert(0, "0".to_string());
+        }
+        // The cron crate numbers days of the week from Sunday as 1.
+        if let Some(days) = fields.get_mut(5) {
+            *days = cron_weekdays(days).map_err(|e| format!("Invalid schedule `{value}`: {e}"))?;
+        }
+        fields
+            .join(" ")
+            .parse()
+            .map(|schedule| Schedule(Box::new(schedule)))
+            .map_err(|e| format!("Invalid schedule `{value}`: {e}"))
+    }
+}
+
+/// Convert a crontab day of week field to the numbering used by the cron crate.
+///
+/// Items are expanded to a list of days, so names and numbers can be mixed. `*` is left as it
+/// is.
+fn cron_weekdays(field: &str) -> Result<String, String> {
+    const NAMES: [(&str, &str); 7] = [
+        ("sun", "sunday"),
+        ("mon", "monday"),
+        ("tue", "tuesday"),
+        ("wed", "wednesday"),
+        ("thu", "thursday"),
+        ("fri", "friday"),
+        ("sat", "saturday"),
+    ];
+    let parse_day = |day: &str| {
+        if let Ok(number) = day.parse::<u32>() {
+            return (number <= 7)
+                .then_some(number)
+                .ok_or_else(|| format!("Invalid day of week `{day}`"));
+        }
+        let name = day.to_ascii_lowercase();
+        (0..)
+            .zip(NAMES)
+            .find(|(_, (short, full))| name == *short || name == *full)
+            .map(|(number, _)| number)
+            .ok_or_else(|| format!("Invalid day of week `{day}`"))
+    };
+    let mut items = vec![];
+    for item in field.split(',') {
+        if item == "*" || item == "?" {
+            items.push(item.to_string());
+            continue;
+        }
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => (range, Some(step)),
+            None => (item, None),
+        };
+        let step = match step {
+            Some(step) => step
+                .parse::<usize>()
+                .ok()
+                .filter(|&step| step > 0)
+                .ok_or_else(|| format!("Invalid step `{step}`"))?,
+            None => 1,
+        };
+        let (start, end) = match range.split_once('-') {
+            _ if range == "*" => (0, 6),
+            Some((start, end)) => match (parse_day(start)?, parse_day(end)?) {
+                // Ranges can end on Sunday, as in `FRI-SUN`.
+                (start, 0) if start > 0 => (start, 7),
+                range => range,
+            },
+            None if step > 1 => (parse_day(range)?, 6),
+            None => (parse_day(range)?, parse_day(range)?),
+        };
+        if start > end {
+            return Err(format!("Invalid day of week range `{range}`"));
+        }
+        for day in (start..=end).step_by(step) {
+            items.push((day % 7 + 1).to_string());
+        }
+    }
+
+    Ok(items.join(","))
+}
+
+#[derive(Deserialize, JsonSchema, Debug, Clone)]
 #[serde(rename_all = "kebab-case", deny_unknown_fields)]
 pub struct InotifyConfig {
     pub template: Option<String>,
+    #[serde(deserialize_with = "expand::path")]
     pub file: std::path::PathBuf,
     #[serde(default)]
     pub json: bool,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, JsonSchema, Debug, Clone)]
 #[serde(rename_all = "kebab-case", deny_unknown_fields)]
 pub struct NetworkConfig {
     pub template: Option<String>,
     pub device: String,
+    #[serde(default, deserialize_with = "expand::optional_path")]
+    pub proc_root: Option<PathBuf>,
+    #[serde(default, deserialize_with = "expand::optional_path")]
+    pub sys_root: Option<PathBuf>,
 }
 
+pub const DEFAULT_PROC_ROOT: &str = "/proc";
+pub const DEFAULT_SYS_ROOT: &str = "/sys";
+
 const fn default_interval() -> u64 {
     1
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, JsonSchema, Debug, Clone)]
 #[serde(rename_all = "kebab-case", deny_unknown_fields)]
 pub struct NetworkStatsConfig {
     pub template: Option<String>,
     pub device: String,
     #[serde(default = "default_interval")]
     pub interval: u64,
+    #[serde(default, deserialize_with = "expand::optional_path")]
+    pub sys_root: Option<PathBuf>,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, JsonSchema, Debug, Clone)]
 #[serde(rename_all = "kebab-case", deny_unknown_fields)]
 pub struct PulseVolumeConfig {
     pub template: Option<String>,
     pub sink_name: Option<String>,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, JsonSchema, Debug, Clone)]
 #[serde(rename_all = "kebab-case", deny_unknown_fields)]
 pub struct SignalConfig {
     pub template: Option<String>,
+    #[serde(deserialize_with = "expand::string")]
     pub command: String,
-    #[serde(default)]
+    #[serde(default, deserialize_with = "expand::strings")]
     pub args: Vec<String>,
     pub signal: RTSigNum,
     #[serde(default)]
     pub json: bool,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone, Copy)]
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, Copy)]
 #[serde(try_from = "i32")]
 pub struct RTSigNum(pub i32);
 
@@ -139,7 +886,50 @@ impl TryFrom<i32> for RTSigNum {
     }
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, JsonSchema, Debug, Clone)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct PluginConfig {
+    pub template: Option<String>,
+    #[serde(deserialize_with = "expand::string")]
+    pub command: String,
+    #[serde(default, deserialize_with = "expand::strings")]
+    pub args: Vec<String>,
+    /// Sent to the plugin when it starts.
+    #[serde(default)]
+    #[schemars(with = "BTreeMap<String, serde_json::Value>")]
+    pub config: BTreeMap<String, toml::Value>,
+    /// Ask the plugin to refresh this often, in seconds.
+    pub interval: Option<u64>,
+    /// Ask the plugin to refresh when this signal is received.
+    pub signal: Option<RTSigNum>,
+    /// Forward i3bar click events for the block from stdin to the plugin.
+    #[serde(default)]
+    pub clicks: bool,
+}
+
+#[derive(Deserialize, JsonSchema, Debug, Clone)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ScriptConfig {
+    pub template: Option<String>,
+    /// Rhai source to run. Exactly one of `script` and `script-file` must be set.
+    pub script: Option<String>,
+    /// File to load the Rhai source from, instead of `script`.
+    #[serde(default, deserialize_with = "expand::optional_path")]
+    pub script_file: Option<std::path::PathBuf>,
+    /// Run the script again this often, in seconds.
+    pub interval: Option<u64>,
+    /// Run the script again when this signal is received.
+    pub signal: Option<RTSigNum>,
+    /// Stop the script if it runs for longer than this, in seconds.
+    #[serde(default = "default_script_timeout")]
+    pub timeout: f64,
+}
+
+const fn default_script_timeout() -> f64 {
+    10.0
+}
+
+#[derive(Deserialize, JsonSchema, Debug, Clone)]
 #[serde(rename_all = "kebab-case", deny_unknown_fields)]
 pub struct StdinConfig {
     pub template: Option<String>,
@@ -148,20 +938,22 @@ pub struct StdinConfig {
 }
 
 /// A no-op block that does nothing. Used when no other blocks are configured.
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, JsonSchema, Debug, Clone)]
 #[serde(rename_all = "kebab-case", deny_unknown_fields)]
 pub struct NoopConfig {
     pub template: Option<String>,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, JsonSchema, Debug, Clone)]
 #[serde(rename_all = "kebab-case", deny_unknown_fields)]
 pub struct CpuConfig {
     pub template: Option<String>,
     pub interval: u64,
+    #[serde(default, deserialize_with = "expand::optional_path")]
+    pub proc_root: Option<PathBuf>,
 }
 
-#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all = "kebab-case")]
 pub enum Precision {
     Second,
@@ -170,18 +962,246 @@ pub enum Precision {
     Day,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, JsonSchema, Debug, Clone)]
 #[serde(rename_all = "kebab-case", deny_unknown_fields)]
 pub struct DateTimeConfig {
     pub template: Option<String>,
     pub precision: Precision,
     #[serde(default)]
+    #[schemars(with = "Option<String>")]
     pub timezone: Option<chrono_tz::Tz>,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, JsonSchema, Debug, Clone)]
 #[serde(rename_all = "kebab-case", deny_unknown_fields)]
 pub struct TemperatureConfig {
     pub template: Option<String>,
     pub interval: u64,
+    #[serde(default, deserialize_with = "expand::optional_path")]
+    pub sys_root: Option<PathBuf>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use chrono::{Datelike, TimeZone, Utc, Weekday};
+
+    use super::{load_config, parse_blocks, read_config, BlockConfig, Schedule};
+
+    fn block_positions(source: &str) -> Vec<(String, Option<(usize, usize)>)> {
+        let config: toml::Value = toml::from_str(source).unwrap();
+        let raw_blocks = config.get("blocks").unwrap().clone();
+        parse_blocks(raw_blocks, Path::new("config.toml"), source)
+            .into_iter()
+            .map(|result| {
+                let (name, block) = result.unwrap();
+                (name, block.location.position)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn locate_table_blocks() {
+        let source = r#"
+[ blocks.date ]
+kind = "date-time"
+precision = "minute"
+
+[blocks."quoted"]
+kind = "noop"
+
+[blocks]
+inline = { kind = "noop" }
+dotted.kind = "noop"
+"#;
+        assert_eq!(
+            block_positions(source),
+            [
+                ("date".to_string(), Some((2, 10))),
+                ("dotted".to_string(), Some((11, 1))),
+                ("inline".to_string(), Some((10, 1))),
+                ("quoted".to_string(), Some((6, 9))),
+            ]
+        );
+    }
+
+    #[test]
+    fn locate_array_blocks() {
+        let source = r#"
+[[ blocks ]]
+name = "first"
+kind = "noop"
+
+[[blocks]]
+
+kind = "noop"
+name = "second"
+"#;
+        assert_eq!(
+            block_positions(source),
+            [
+                ("first".to_string(), Some((3, 1))),
+                ("second".to_string(), Some((8, 1))),
+            ]
+        );
+        let source = "blocks = [\n  { name = \"a\", kind = \"noop\" },\n]\n";
+        assert_eq!(block_positions(source), [("a".to_string(), Some((2, 3)))]);
+    }
+
+    #[test]
+    fn reserved_block_names() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        let source = "[blocks.blocks]\nkind = \"noop\"\n\n[blocks.states]\nkind = \"noop\"\n\n[blocks.ok]\nkind = \"noop\"\n\n[blocks.vars]\nkind = \"noop\"\n";
+        std::io::Write::write_all(&mut file, source.as_bytes()).unwrap();
+
+        let (config, errors) = read_config(Some(file.path().to_path_buf())).unwrap();
+        let names: Vec<_> = config
+            .blocks
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        assert_eq!(names, ["ok"]);
+        let errors: Vec<_> = errors
+            .iter()
+            .map(|error| (error.block.as_deref(), error.location.position))
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                (Some("blocks"), Some((1, 9))),
+                (Some("states"), Some((4, 9))),
+                (Some("vars"), Some((10, 9))),
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_include() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("config.toml");
+        std::fs::write(&file, "include = [\"blocks.d/*.toml\", \"extra.toml\"]\n").unwrap();
+        let error = load_config(Some(file.clone())).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "Included file {} not found",
+                dir.path().join("extra.toml").display()
+            )
+        );
+
+        std::fs::write(
+            dir.path().join("extra.toml"),
+            "[blocks.extra]\nkind = \"noop\"\n",
+        )
+        .unwrap();
+        let config = load_config(Some(file)).unwrap();
+        assert_eq!(config.blocks[0].0, "extra");
+    }
+
+    #[test]
+    fn temperature_ignores_global_sys_root() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        let source = "sys-root = \"/fake\"\n\n[blocks.net]\nkind = \"network-stats\"\ndevice = \"eth0\"\n\n[blocks.temp]\nkind = \"temperature\"\ninterval = 1\n";
+        std::io::Write::write_all(&mut file, source.as_bytes()).unwrap();
+
+        let config = load_config(Some(file.path().to_path_buf())).unwrap();
+        let sys_roots: Vec<_> = config
+            .blocks
+            .iter()
+            .map(|(name, block)| match &block.config {
+                BlockConfig::NetworkStats(config) => (name.as_str(), config.sys_root.clone()),
+                BlockConfig::Temperature(config) => (name.as_str(), config.sys_root.clone()),
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(sys_roots, [("net", Some("/fake".into())), ("temp", None)]);
+    }
+
+    #[test]
+    fn locate_example_blocks() {
+        let examples = std::fs::read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/examples")).unwrap();
+        for entry in examples {
+            let file = entry.unwrap().path();
+            if file.extension().is_none_or(|extension| extension != "toml") {
+                continue;
+            }
+            let (config, errors) = read_config(Some(file.clone())).unwrap();
+            assert!(!config.blocks.is_empty(), "{}", file.display());
+            for (name, block) in &config.blocks {
+                assert!(
+                    block.location.position.is_some(),
+                    "{}: {name}",
+                    file.display()
+                );
+            }
+            for error in &errors {
+                assert!(error.location.position.is_some(), "{error}");
+            }
+        }
+    }
+
+    fn next_runs(expression: &str, count: usize) -> Vec<chrono::DateTime<Utc>> {
+        let schedule = Schedule::try_from(expression.to_string()).unwrap();
+        // A Monday.
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+        schedule.0.after(&start).take(count).collect()
+    }
+
+    fn weekdays(expression: &str, count: usize) -> Vec<Weekday> {
+        next_runs(expression, count)
+            .iter()
+            .map(|time| time.weekday())
+            .collect()
+    }
+
+    #[test]
+    fn five_fields_run_on_the_minute() {
+        let runs = next_runs("30 2 * * *", 2);
+        assert_eq!(runs[0], Utc.with_ymd_and_hms(2024, 1, 2, 2, 30, 0).unwrap());
+        assert_eq!(runs[1], Utc.with_ymd_and_hms(2024, 1, 3, 2, 30, 0).unwrap());
+    }
+
+    #[test]
+    fn sunday_is_zero_or_seven() {
+        for expression in ["0 2 * * 0", "0 2 * * 7", "0 2 * * SUN"] {
+            let runs = next_runs(expression, 2);
+            assert_eq!(runs[0], Utc.with_ymd_and_hms(2024, 1, 7, 2, 0, 0).unwrap());
+            assert_eq!(runs[1], Utc.with_ymd_and_hms(2024, 1, 14, 2, 0, 0).unwrap());
+        }
+    }
+
+    #[test]
+    fn weekday_range() {
+        use Weekday::*;
+        assert_eq!(
+            weekdays("0 9 * * 1-5", 7),
+            [Tue, Wed, Thu, Fri, Mon, Tue, Wed]
+        );
+        assert_eq!(weekdays("0 9 * * 5-7", 4), [Fri, Sat, Sun, Fri]);
+        assert_eq!(weekdays("0 9 * * 0,6", 3), [Sat, Sun, Sat]);
+        assert_eq!(weekdays("0 9 * * */2", 4), [Tue, Thu, Sat, Sun]);
+    }
+
+    #[test]
+    fn seconds_field_uses_crontab_weekdays() {
+        let runs = next_runs("15 0 9 * * 1", 1);
+        assert_eq!(runs[0], Utc.with_ymd_and_hms(2024, 1, 8, 9, 0, 15).unwrap());
+    }
+
+    #[test]
+    fn weekday_names() {
+        use Weekday::*;
+        assert_eq!(weekdays("0 9 * * MON-5", 5), [Tue, Wed, Thu, Fri, Mon]);
+        assert_eq!(weekdays("0 9 * * 1-fri", 5), [Tue, Wed, Thu, Fri, Mon]);
+        assert_eq!(weekdays("0 9 * * FRI-SUN", 4), [Fri, Sat, Sun, Fri]);
+        assert_eq!(weekdays("0 9 * * Saturday,2", 3), [Tue, Sat, Tue]);
+    }
+
+    #[test]
+    fn invalid_weekday() {
+        assert!(Schedule::try_from("0 2 * * 8".to_string()).is_err());
+        assert!(Schedule::try_from("0 2 * * 5-1".to_string()).is_err());
+        assert!(Schedule::try_from("0 2 * * MON-XYZ".to_string()).is_err());
+    }
 }
@@ -0,0 +1,126 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Deserializer};
+
+/// Expand a leading `~` and `$VAR`, `${VAR}` and `${VAR:-default}` environment variables.
+///
+/// `$$` is a literal `$`. Unset variables without a default are left as they are, so shell
+/// and awk scripts passed as arguments keep working.
+pub fn expand(s: &str) -> String {
+    let s = match s.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => match std::env::var("HOME") {
+            Ok(home) => format!("{home}{rest}"),
+            Err(_) => s.to_string(),
+        },
+        _ => s.to_string(),
+    };
+
+    let mut result = String::with_capacity(s.len());
+    let mut rest = s.as_str();
+    while let Some(index) = rest.find('$') {
+        result.push_str(&rest[..index]);
+        rest = &rest[index..];
+        let (expanded, len) = expand_var(rest);
+        result.push_str(&expanded);
+        rest = &rest[len..];
+    }
+    result.push_str(rest);
+
+    result
+}
+
+/// Expand the variable at the start of `s`, returning the expansion and the length consumed.
+fn expand_var(s: &str) -> (String, usize) {
+    if s.starts_with("$$") {
+        return ("$".to_string(), 2);
+    }
+    if let Some(braced) = s.strip_prefix("${") {
+        if let Some(end) = braced.find('}') {
+            let (name, default) = match braced[..end].split_once(":-") {
+                Some((name, default)) => (name, Some(default)),
+                None => (&braced[..end], None),
+            };
+            if is_var_name(name) {
+                let len = end + 3;
+                return match (std::env::var(name), default) {
+                    (Ok(value), None) => (value, len),
+                    (Ok(value), Some(_)) if !value.is_empty() => (value, len),
+                    (_, Some(default)) => (expand(default), len),
+                    (Err(_), None) => (s[..len].to_string(), len),
+                };
+            }
+        }
+    } else {
+        let len = 1 + s[1..]
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(s.len() - 1);
+        if is_var_name(&s[1..len]) {
+            if let Ok(value) = std::env::var(&s[1..len]) {
+                return (value, len);
+            }
+            return (s[..len].to_string(), len);
+        }
+    }
+
+    ("$".to_string(), 1)
+}
+
+fn is_var_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+pub fn string<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    Ok(expand(&String::deserialize(deserializer)?))
+}
+
+pub fn strings<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    Ok(Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|s| expand(s))
+        .collect())
+}
+
+pub fn path<'de, D: Deserializer<'de>>(deserializer: D) -> Result<PathBuf, D::Error> {
+    Ok(PathBuf::from(expand(&String::deserialize(deserializer)?)))
+}
+
+pub fn optional_path<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<PathBuf>, D::Error> {
+    Ok(Option::<String>::deserialize(deserializer)?.map(|s| PathBuf::from(expand(&s))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::expand;
+
+    #[test]
+    fn set_variables() {
+        std::env::set_var("YABLOCKS_TEST_SET", "value");
+        assert_eq!(expand("$YABLOCKS_TEST_SET/a"), "value/a");
+        assert_eq!(expand("${YABLOCKS_TEST_SET}b"), "valueb");
+        assert_eq!(expand("${YABLOCKS_TEST_SET:-default}"), "value");
+    }
+
+    #[test]
+    fn unset_variables() {
+        std::env::remove_var("YABLOCKS_TEST_UNSET");
+        assert_eq!(expand("$YABLOCKS_TEST_UNSET"), "$YABLOCKS_TEST_UNSET");
+        assert_eq!(expand("${YABLOCKS_TEST_UNSET}"), "${YABLOCKS_TEST_UNSET}");
+        assert_eq!(expand("${YABLOCKS_TEST_UNSET:-default}"), "default");
+        assert_eq!(expand("{print $2}"), "{print $2}");
+    }
+
+    #[test]
+    fn escaped_dollars() {
+        std::env::set_var("YABLOCKS_TEST_ESCAPED", "value");
+        assert_eq!(expand("$$YABLOCKS_TEST_ESCAPED"), "$YABLOCKS_TEST_ESCAPED");
+        assert_eq!(
+            expand("$${YABLOCKS_TEST_ESCAPED}"),
+            "${YABLOCKS_TEST_ESCAPED}"
+        );
+        assert_eq!(expand("echo $$$YABLOCKS_TEST_ESCAPED"), "echo $value");
+        assert_eq!(expand("cost: $$5"), "cost: $5");
+    }
+}
@@ -0,0 +1,416 @@
+use anyhow::Result;
+use schemars::generate::SchemaSettings;
+use schemars::transform::transform_subschemas;
+use schemars::{JsonSchema, Schema};
+use serde_json::{json, Value};
+
+use crate::block_stream;
+use crate::config::{BlockOptions, Config};
+
+/// Print the inputs and outputs of one block kind (or all of them), or a JSON Schema of the
+/// config.
+pub fn run(kind: Option<String>, schema: bool) -> Result<()> {
+    if schema {
+        println!("{}", serde_json::to_string_pretty(&config_schema())?);
+        return Ok(());
+    }
+
+    let kinds = block_stream::describe_kinds();
+    match kind {
+        Some(kind) => {
+            let description = kinds
+                .iter()
+                .find(|description| description.kind == kind)
+                .ok_or_else(|| anyhow::anyhow!("Unknown block kind `{kind}`"))?;
+            print!("{description}");
+        }
+        None => {
+            println!("Options for all blocks:\n");
+            print!("{}", format_fields(&schema_for::<BlockOptions>()));
+            for description in &kinds {
+                print!("\n{description}");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// A block kind, along with the schemas of its config and the data passed to its template.
+pub struct KindDescription {
+    pub kind: &'static str,
+    pub inputs: Value,
+    pub outputs: Value,
+}
+
+impl std::fmt::Display for KindDescription {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "{}\n\n  Inputs:", self.kind)?;
+        write!(f, "{}", format_fields(&self.inputs))?;
+        writeln!(f, "\n  Outputs:")?;
+        write!(f, "{}", format_fields(&self.outputs))
+    }
+}
+
+/// A self-contained JSON Schema for `T`, with every subschema inlined.
+pub fn schema_for<T: JsonSchema>() -> Value {
+    let mut settings = SchemaSettings::draft07();
+    settings.inline_subschemas = true;
+    settings
+        .transforms
+        .push(Box::new(remove_null as fn(&mut Schema)));
+    let mut schema = settings
+        .into_generator()
+        .into_root_schema_for::<T>()
+        .to_value();
+    if let Some(schema) = schema.as_object_mut() {
+        schema.remove("$schema");
+        schema.remove("title");
+    }
+
+    schema
+}
+
+/// Remove `null` from the types a schema allows.
+///
+/// TOML has no null, so optional values can only be left out.
+fn remove_null(schema: &mut Schema) {
+    transform_subschemas(&mut remove_null, schema);
+    let Some(object) = schema.as_object_mut() else {
+        return;
+    };
+    if let Some(Value::Array(types)) = object.get_mut("type") {
+        types.retain(|type_name| type_name != "null");
+        if let [type_name] = types.as_slice() {
+            let type_name = type_name.clone();
+            object.insert("type".to_string(), type_name);
+        }
+    }
+    if let Some(Value::Array(values)) = object.get_mut("enum") {
+        values.retain(|value| !value.is_null());
+    }
+    if object.get("default") == Some(&Value::Null) {
+        object.remove("default");
+    }
+    let keys = object.len();
+    if let Some(Value::Array(schemas)) = object.get_mut("anyOf") {
+        schemas.retain(|schema| schema.get("type") != Some(&json!("null")));
+        if let ([inner], 1) = (schemas.as_slice(), keys) {
+            if let Ok(inner) = Schema::try_from(inner.clone()) {
+                *schema = inner;
+            }
+        }
+    }
+}
+
+/// Format the properties of an object schema as an aligned list of names and types.
+fn format_fields(schema: &Value) -> String {
+    let mut rows = vec![];
+    field_rows("", schema, &mut rows);
+    if rows.is_empty() {
+        return "    (none)\n".to_string();
+    }
+    let width = rows.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    rows.iter()
+        .map(|(name, type_name)| format!("    {name:width$}  {type_name}\n"))
+        .collect()
+}
+
+/// List each property of an object schema with its type, using dotted names for nested fields.
+fn field_rows(prefix: &str, schema: &Value, rows: &mut Vec<(String, String)>) {
+    let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+        return;
+    };
+    let required = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+    for (key, property) in properties {
+        let name = format!("{prefix}{key}");
+        let type_name = if required.contains(&json!(key)) {
+            type_name(property)
+        } else {
+            format!("{} (optional)", type_name(property))
+        };
+        rows.push((name.clone(), type_name));
+
+        // Describe the fields of objects nested in arrays and maps too.
+        let mut nested = (name, property);
+        loop {
+            nested = match (nested.1.get("items"), nested.1.get("additionalProperties")) {
+                (Some(items), _) if items.is_object() => (format!("{}[]", nested.0), items),
+                (_, Some(values)) if values.is_object() => (format!("{}.*", nested.0), values),
+                _ => break,
+            };
+        }
+        field_rows(&format!("{}.", nested.0), nested.1, rows);
+    }
+}
+
+/// A short name for the type of values a schema allows.
+fn type_name(schema: &Value) -> String {
+    let join = |values: &Vec<Value>, name: fn(&Value) -> String| {
+        values.iter().map(name).collect::<Vec<_>>().join(" | ")
+    };
+    if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+        return join(values, Value::to_string);
+    }
+    if let Some(value) = schema.get("const") {
+        return value.to_string();
+    }
+    if let Some(schemas) = schema.get("anyOf").or(schema.get("oneOf")) {
+        if let Some(schemas) = schemas.as_array() {
+            return join(schemas, type_name);
+        }
+    }
+    match schema.get("type").and_then(Value::as_str) {
+        Some("array") => format!("array({})", type_name(&schema["items"])),
+        Some("object") if schema.get("properties").is_some() => "table".to_string(),
+        Some("object") => format!("map({})", type_name(&schema["additionalProperties"])),
+        Some(type_name) => type_name.to_string(),
+        None => "any".to_string(),
+    }
+}
+
+/// A JSON Schema for the config file.
+fn config_schema() -> Value {
+    let mut schema = schema_for::<Config>();
+    schema["$schema"] = json!("http://json-schema.org/draft-07/schema#");
+    schema["title"] = json!("yablocks config");
+    schema["properties"]["blocks"] = json!({
+        "oneOf": [
+            {"type": "object", "additionalProperties": {"$ref": "#/definitions/block"}},
+            {"type": "array", "items": {"$ref": "#/definitions/named-block"}},
+        ]
+    });
+    schema["definitions"] = json!({
+        "block": block_schema(false),
+        "named-block": block_schema(true),
+    });
+
+    schema
+}
+
+/// Each kind's config, combined with the options common to all blocks.
+fn block_schema(named: bool) -> Value {
+    let options = schema_for::<BlockOptions>();
+    let kinds: Vec<_> = block_stream::describe_kinds()
+        .into_iter()
+        .map(|description| {
+            let mut schema = description.inputs;
+            if let (Some(properties), Some(options)) = (
+                schema["properties"].as_object_mut(),
+                options["properties"].as_object(),
+            ) {
+                properties.extend(options.clone());
+            }
+            schema["properties"]["kind"] = json!({ "const": description.kind });
+            let mut required = vec![json!("kind")];
+            if named {
+                schema["properties"]["name"] = json!({ "type": "string" });
+                required.push(json!("name"));
+            }
+            if let Some(Value::Array(fields)) = schema.get_mut("required") {
+                required.append(fields);
+            }
+            schema["required"] = Value::Array(required);
+            schema
+        })
+        .collect();
+
+    json!({ "oneOf": kinds })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{config_schema, format_fields, schema_for};
+    use crate::block_stream;
+
+    fn describe(kind: &str) -> String {
+        block_stream::describe_kinds()
+            .iter()
+            .find(|description| description.kind == kind)
+            .unwrap()
+            .to_string()
+    }
+
+    fn lines(lines: &[&str]) -> String {
+        lines.iter().map(|line| format!("{line}\n")).collect()
+    }
+
+    #[test]
+    fn describe_interval() {
+        // `command` and `args` use custom deserializers, and `schedule` converts from a string.
+        assert_eq!(
+            describe("interval"),
+            lines(&[
+                "interval",
+                "",
+                "  Inputs:",
+                "    align     boolean (optional)",
+                "    args      array(string) (optional)",
+                "    command   string",
+                "    interval  integer (optional)",
+                "    json      boolean (optional)",
+                "    schedule  string (optional)",
+                "    template  string (optional)",
+                "",
+                "  Outputs:",
+                "    args      array(string)",
+                "    command   string",
+                "    interval  integer (optional)",
+                "    output    any",
+                "    status    integer",
+            ])
+        );
+    }
+
+    #[test]
+    fn describe_signal() {
+        // `signal` converts from an integer.
+        assert_eq!(
+            describe("signal"),
+            lines(&[
+                "signal",
+                "",
+                "  Inputs:",
+                "    args      array(string) (optional)",
+                "    command   string",
+                "    json      boolean (optional)",
+                "    signal    integer",
+                "    template  string (optional)",
+                "",
+                "  Outputs:",
+                "    args     array(string)",
+                "    command  string",
+                "    output   any",
+                "    signal   integer",
+                "    status   integer",
+            ])
+        );
+    }
+
+    #[test]
+    fn describe_date_time() {
+        let description = describe("date-time");
+        assert!(description.starts_with(&lines(&[
+            "date-time",
+            "",
+            "  Inputs:",
+            "    precision  \"second\" | \"minute\" | \"hour\" | \"day\"",
+            "    template   string (optional)",
+            "    timezone   string (optional)",
+            "",
+            "  Outputs:",
+            "    am_pm                  string",
+        ])));
+    }
+
+    #[test]
+    fn describe_nested_outputs() {
+        assert!(describe("temperature").ends_with(&lines(&[
+            "  Outputs:",
+            "    chips            map(array(table))",
+            "    chips.*[].chip   string",
+            "    chips.*[].label  string",
+            "    chips.*[].value  number",
+        ])));
+        assert!(describe("noop").ends_with(&lines(&["  Outputs:", "    (none)"])));
+    }
+
+    #[test]
+    fn describe_block_options() {
+        assert_eq!(
+            format_fields(&schema_for::<crate::config::BlockOptions>()),
+            lines(&[
+                "    enabled-if                  table (optional)",
+                "    enabled-if.env              string (optional)",
+                "    enabled-if.hostname         string (optional)",
+                "    enabled-if.path-exists      string (optional)",
+                "    history                     integer (optional)",
+                "    marquee                     table (optional)",
+                "    marquee.interval            number (optional)",
+                "    marquee.separator           string (optional)",
+                "    marquee.width               integer",
+                "    template-file               string (optional)",
+                "    thresholds                  table (optional)",
+                "    thresholds.colors           table (optional)",
+                "    thresholds.colors.critical  string (optional)",
+                "    thresholds.colors.good      string (optional)",
+                "    thresholds.colors.idle      string (optional)",
+                "    thresholds.colors.info      string (optional)",
+                "    thresholds.colors.warning   string (optional)",
+                "    thresholds.critical         number (optional)",
+                "    thresholds.field            string",
+                "    thresholds.good             number (optional)",
+                "    thresholds.info             number (optional)",
+                "    thresholds.warning          number (optional)",
+            ])
+        );
+    }
+
+    #[test]
+    fn optional_values_are_not_null() {
+        let schema = schema_for::<crate::config::BlockOptions>();
+        assert_eq!(
+            schema["properties"]["template-file"],
+            json!({
+                "description": "File to load the block's template from, instead of `template`.",
+                "type": "string",
+            })
+        );
+        assert_eq!(
+            schema["properties"]["thresholds"]["properties"]["good"],
+            json!({ "type": "number", "format": "double" })
+        );
+    }
+
+    #[test]
+    fn schema() {
+        let schema = config_schema();
+        let properties = schema["properties"].as_object().unwrap();
+        let keys: Vec<_> = properties.keys().map(String::as_str).collect();
+        assert_eq!(
+            keys,
+            [
+                "autoescape",
+                "blocks",
+                "header",
+                "include",
+                "palette",
+                "proc-root",
+                "separator",
+                "stdin-handler",
+                "sys-root",
+                "template",
+                "template-file",
+                "templates-dir",
+                "theme-file",
+                "vars",
+            ]
+        );
+        assert_eq!(properties["stdin-handler"]["required"], json!(["command"]));
+
+        // Each named block combines the common options with its kind's config.
+        let signal = schema["definitions"]["named-block"]["oneOf"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|block| block["properties"]["kind"]["const"] == "signal")
+            .unwrap();
+        assert_eq!(
+            signal["required"],
+            json!(["kind", "name", "command", "signal"])
+        );
+        assert_eq!(signal["properties"]["signal"]["type"], "integer");
+        assert_eq!(signal["properties"]["history"]["type"], "integer");
+        assert_eq!(
+            signal["properties"]["marquee"]["required"],
+            json!(["width"])
+        );
+        assert_eq!(signal["additionalProperties"], false);
+    }
+}
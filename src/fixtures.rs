@@ -0,0 +1,186 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+
+use crate::block_stream;
+use crate::config::{self, Config};
+use crate::RENDERER;
+
+/// Render block and root templates from fixture data, and compare the output to snapshots.
+///
+/// Each `<block>/<case>.json` file in `dir` holds data for the block called `<block>`, and
+/// each `<case>.json` file directly in `dir` holds an object mapping block names to data for
+/// the root template. Data can also be an array, which is rendered in order so that history
+/// and thresholds can be tested. The expected output of each case is in `<case>.txt`.
+pub fn run(file: Option<PathBuf>, dir: Option<PathBuf>, update: bool) -> Result<()> {
+    let config = config::load_config(file).context("Failed to load config")?;
+    RENDERER.configure(&config)?;
+    for (name, block) in &config.blocks {
+        RENDERER.set_block_options(name, &block.options);
+        block_stream::add_template(name, block)
+            .with_context(|| format!("Failed to load template for block '{name}'"))?;
+    }
+    RENDERER
+        .set_root_template(&config)
+        .context("Failed to build template renderer")?;
+
+    let dir = match dir {
+        Some(dir) => dir,
+        None => config.file.with_file_name("fixtures"),
+    };
+    let cases = find_cases(&dir)
+        .with_context(|| format!("Failed to read fixtures dir {}", dir.display()))?;
+    anyhow::ensure!(!cases.is_empty(), "No fixtures found in {}", dir.display());
+
+    let mut failures = 0;
+    for (block, fixture) in &cases {
+        let case = fixture
+            .strip_prefix(&dir)
+            .unwrap_or(fixture)
+            .with_extension("");
+        let result = match block {
+            Some(name) => run_block_case(&config, name, fixture),
+            None => run_root_case(&config, fixture),
+        };
+        let output = match result {
+            Ok(output) => output,
+            Err(error) => {
+                failures += 1;
+                println!("ERROR {}: {error:#}", case.display());
+                continue;
+            }
+        };
+        let snapshot = fixture.with_extension("txt");
+        if update {
+            std::fs::write(&snapshot, format!("{output}\n"))
+                .with_context(|| format!("Failed to write {}", snapshot.display()))?;
+            println!("updated {}", case.display());
+            continue;
+        }
+        let expected = match std::fs::read_to_string(&snapshot) {
+            Ok(expected) => expected,
+            Err(_) => {
+                failures += 1;
+                println!(
+                    "MISSING {}: run with --update to create it",
+                    snapshot.display()
+                );
+                continue;
+            }
+        };
+        let expected = expected.strip_suffix('\n').unwrap_or(&expected);
+        if output == expected {
+            println!("ok {}", case.display());
+        } else {
+            failures += 1;
+            println!("FAIL {}", case.display());
+            print!(
+                "{}",
+                similar::TextDiff::from_lines(expected, &output)
+                    .unified_diff()
+                    .missing_newline_hint(false)
+                    .header("expected", "actual")
+            );
+        }
+    }
+
+    match failures {
+        0 => {
+            println!("{} passed", cases.len());
+            Ok(())
+        }
+        1 => anyhow::bail!("1 of {} failed", cases.len()),
+        count => anyhow::bail!("{count} of {} failed", cases.len()),
+    }
+}
+
+/// Find every fixture in `dir`, along with the block it's for (or `None` for root fixtures).
+fn find_cases(dir: &Path) -> Result<Vec<(Option<String>, PathBuf)>> {
+    let mut cases = vec![];
+    for path in json_files(dir)? {
+        cases.push((None, path));
+    }
+    let mut subdirs: Vec<_> = std::fs::read_dir(dir)?
+        .map(|entry| Ok(entry?.path()))
+        .collect::<Result<_>>()?;
+    subdirs.retain(|path| path.is_dir());
+    subdirs.sort();
+    for subdir in subdirs {
+        let name = subdir.file_name().unwrap_or_default().to_string_lossy();
+        for path in json_files(&subdir)? {
+            cases.push((Some(name.to_string()), path));
+        }
+    }
+
+    Ok(cases)
+}
+
+fn json_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files: Vec<_> = std::fs::read_dir(dir)?
+        .map(|entry| Ok(entry?.path()))
+        .collect::<Result<_>>()?;
+    files.retain(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "json"));
+    files.sort();
+
+    Ok(files)
+}
+
+fn read_fixture(file: &Path) -> Result<Value> {
+    let contents = std::fs::read_to_string(file)?;
+    Ok(serde_json::from_str(&contents)?)
+}
+
+fn run_block_case(config: &Config, name: &str, fixture: &Path) -> Result<String> {
+    reset_blocks(config);
+    render_block(config, name, read_fixture(fixture)?)
+}
+
+fn run_root_case(config: &Config, fixture: &Path) -> Result<String> {
+    reset_blocks(config);
+    let data = match read_fixture(fixture)? {
+        Value::Object(data) => data,
+        _ => anyhow::bail!("Expected an object mapping block names to data"),
+    };
+    let mut outputs: BTreeMap<_, _> = config
+        .blocks
+        .iter()
+        .map(|(name, _)| (name.clone(), String::new()))
+        .collect();
+    for (name, data) in data {
+        let output = render_block(config, &name, data)
+            .with_context(|| format!("Failed to render block '{name}'"))?;
+        outputs.insert(name, output);
+    }
+
+    RENDERER.render_root(&outputs)
+}
+
+fn reset_blocks(config: &Config) {
+    for (name, _) in &config.blocks {
+        RENDERER.reset_block(name);
+    }
+}
+
+/// Render each item of `data` in turn (or `data` itself if it isn't an array), returning the
+/// final output.
+fn render_block(config: &Config, name: &str, data: Value) -> Result<String> {
+    let block = config
+        .blocks
+        .iter()
+        .find(|(block_name, _)| block_name == name)
+        .map(|(_, block)| block)
+        .with_context(|| format!("No block named '{name}'"))?;
+    let items = match data {
+        Value::Array(items) => items,
+        data => vec![data],
+    };
+    let mut output = String::new();
+    for data in items {
+        let data = block_stream::parse_data(&block.config, data)?;
+        output = RENDERER.render(name, data)?;
+    }
+
+    Ok(output)
+}
@@ -0,0 +1,172 @@
+//! Yet another block-based status bar generator.
+//!
+//! The `yablocks` binary is a thin wrapper around [`cli::run`]. Programs using yablocks as a
+//! library can add their own block kinds with [`register_block`] before calling it.
+
+mod block_stream;
+mod check;
+pub mod cli;
+pub mod config;
+mod describe;
+mod fixtures;
+mod preview;
+mod recording;
+pub mod renderer;
+mod stdin_handler;
+mod theme;
+mod watcher;
+
+use std::collections::{BTreeMap, HashSet};
+
+use anyhow::{Context, Result};
+use futures::stream::select_all::select_all;
+use futures::{FutureExt, StreamExt};
+
+pub use block_stream::{register_block, BlockStream, BlockStreamConfig};
+pub use renderer::{RENDERER, ROOT_TEMPLATE};
+/// Block kinds added with [`register_block`] derive [`schemars::JsonSchema`] for their config
+/// and data.
+pub use schemars;
+
+static DEBOUNCE_TIME: std::time::Duration = std::time::Duration::from_millis(10);
+
+/// Wait for the first output of each of `names` (or `timeout`), then print the root template.
+async fn run_once(
+    config: config::Config,
+    names: Vec<String>,
+    block_streams: Vec<BlockStream>,
+    timeout: std::time::Duration,
+) -> Result<()> {
+    let mut context: BTreeMap<_, _> = config
+        .blocks
+        .iter()
+        .map(|(name, _)| (name.clone(), "".to_string()))
+        .collect();
+    let mut pending: HashSet<_> = names.into_iter().collect();
+    let mut stream = select_all(block_streams);
+    let deadline = tokio::time::sleep(timeout);
+    tokio::pin!(deadline);
+    while !pending.is_empty() {
+        tokio::select! {
+            item = stream.next() => {
+                let (name, result) = match item {
+                    Some(item) => item,
+                    None => break,
+                };
+                match result {
+                    Ok(value) => {
+                        context.insert(name.clone(), value);
+                    }
+                    Err(error) => eprintln!("Error from {name}: {error:?}"),
+                };
+                pending.remove(&name);
+            }
+            _ = &mut deadline => {
+                let mut pending: Vec<_> = pending.into_iter().collect();
+                pending.sort();
+                eprintln!("Timed out waiting for {}", pending.join(", "));
+                break;
+            }
+        }
+    }
+
+    let output = RENDERER
+        .render_root(&context)
+        .context("Failed to render template")?;
+    println!("{output}");
+
+    Ok(())
+}
+
+/// Print the root template every time one of `block_streams` updates.
+pub async fn run_blocks(config: config::Config, mut block_streams: Vec<BlockStream>) -> Result<()> {
+    let config::Config {
+        header,
+        palette,
+        theme_file,
+        blocks: block_configs,
+        ..
+    } = config;
+    let mut theme_watcher = watcher::FileWatcher::new(theme_file.iter().cloned().collect())
+        .context("Failed to watch theme file")?;
+
+    // Initialize the context so we can start rendering immediately
+    let mut context = BTreeMap::new();
+    for (name, _) in &block_configs {
+        context.insert(name.clone(), "".to_string());
+    }
+
+    // If no blocks are configured, add a noop block that sleeps forever.
+    if block_streams.is_empty() {
+        let noop_stream = config::BlockConfig::Noop(config::NoopConfig { template: None })
+            .to_stream("noop".to_string())?;
+        block_streams.push(noop_stream);
+    }
+
+    let mut stream = select_all(block_streams);
+    let mut template_watcher = watcher::FileWatcher::new(RENDERER.template_paths())
+        .context("Failed to watch template files")?;
+
+    if let Some(header) = header {
+        println!("{header}");
+    }
+    loop {
+        tokio::select! {
+            item = stream.next() => {
+                let (name, result) = match item {
+                    Some(item) => item,
+                    None => break,
+                };
+                match result {
+                    Ok(value) => context.insert(name, value),
+                    Err(error) => {
+                        eprintln!("Error from {name}: {error:?}");
+                        continue;
+                    }
+                };
+            }
+            _ = theme_watcher.changed() => {
+                match theme::build_palette(theme_file.as_deref(), &palette) {
+                    Ok(colors) => RENDERER.set_palette(colors),
+                    Err(error) => {
+                        eprintln!("{error:?}");
+                        continue;
+                    }
+                }
+                rerender_blocks(&mut context);
+            }
+            _ = template_watcher.changed() => {
+                if let Err(error) = RENDERER.reload_templates() {
+                    eprintln!("Failed to reload templates: {error:?}");
+                    continue;
+                }
+                rerender_blocks(&mut context);
+            }
+        }
+        tokio::time::sleep(DEBOUNCE_TIME).await;
+        while let Some((name, result)) = stream.next().now_or_never().flatten() {
+            match result {
+                Ok(value) => {
+                    context.insert(name, value);
+                }
+                Err(error) => eprintln!("Error from {name}: {error:?}"),
+            };
+        }
+        let output = RENDERER
+            .render_root(&context)
+            .context("Failed to render template")?;
+        println!("{output}");
+    }
+    Ok(())
+}
+
+/// Re-render every block from its most recent data.
+fn rerender_blocks(context: &mut BTreeMap<String, String>) {
+    for (name, value) in context.iter_mut() {
+        match RENDERER.rerender(name) {
+            Some(Ok(output)) => *value = output,
+            Some(Err(error)) => eprintln!("Error from {name}: {error:?}"),
+            None => {}
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use futures::StreamExt;
+
+use crate::block_stream::{self, BlockStreamConfig};
+use crate::{config, watcher, RENDERER};
+
+enum Event {
+    Update(Result<String>),
+    ConfigChanged,
+    TemplatesChanged,
+}
+
+/// Run a single block, printing its data and rendered output every time it updates.
+///
+/// The block's template is reloaded whenever the config file or its template file changes.
+pub async fn run(file: Option<PathBuf>, name: String) -> Result<()> {
+    let config = config::load_config(file).context("Failed to load config")?;
+    RENDERER.configure(&config)?;
+    let config_file = config.file.clone();
+    let mut block = find_block(config, &name)?;
+    // Marquee output would bury the data between updates.
+    block.options.marquee = None;
+
+    let mut stream = block
+        .to_stream(name.clone())
+        .with_context(|| format!("Failed to initialize block '{name}'"))?;
+    let mut config_watcher = watcher::FileWatcher::new(vec![config_file.clone()])
+        .context("Failed to watch config file")?;
+    let mut template_watcher = watcher::FileWatcher::new(RENDERER.template_paths())
+        .context("Failed to watch template files")?;
+
+    loop {
+        let event = tokio::select! {
+            item = stream.next() => match item {
+                Some((_, result)) => Event::Update(result),
+                None => break,
+            },
+            _ = config_watcher.changed() => Event::ConfigChanged,
+            _ = template_watcher.changed() => Event::TemplatesChanged,
+        };
+        match event {
+            Event::Update(result) => print_update(&name, result),
+            Event::ConfigChanged => {
+                if let Err(error) = reload_block(&config_file, &name) {
+                    eprintln!("Failed to reload block '{name}': {error:?}");
+                    continue;
+                }
+                template_watcher = watcher::FileWatcher::new(RENDERER.template_paths())
+                    .context("Failed to watch template files")?;
+                if let Some(result) = RENDERER.rerender(&name) {
+                    print_update(&name, result);
+                }
+            }
+            Event::TemplatesChanged => {
+                if let Err(error) = RENDERER.reload_templates() {
+                    eprintln!("Failed to reload templates: {error:?}");
+                    continue;
+                }
+                if let Some(result) = RENDERER.rerender(&name) {
+                    print_update(&name, result);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn find_block(config: config::Config, name: &str) -> Result<config::Block> {
+    let names: Vec<_> = config.blocks.iter().map(|(name, _)| name.clone()).collect();
+    config
+        .blocks
+        .into_iter()
+        .find(|(block_name, _)| block_name == name)
+        .map(|(_, block)| block)
+        .with_context(|| {
+            format!(
+                "No block named '{name}' (available blocks: {})",
+                names.join(", ")
+            )
+        })
+}
+
+/// Reload the block's options and template from the config file.
+fn reload_block(file: &Path, name: &str) -> Result<()> {
+    let config = config::load_config(Some(file.to_path_buf()))?;
+    let block = find_block(config, name)?;
+    RENDERER.set_block_options(name, &block.options);
+    block_stream::add_template(name, &block)
+}
+
+fn print_update(name: &str, result: Result<String>) {
+    let data = RENDERER.last_data(name).unwrap_or_default();
+    match serde_json::to_string_pretty(&data) {
+        Ok(data) => println!("data:\n{data}"),
+        Err(error) => eprintln!("Failed to serialize data: {error:?}"),
+    }
+    match result {
+        Ok(output) => println!("output:\n{output}\n"),
+        Err(error) => eprintln!("Error from {name}: {error:?}\n"),
+    }
+}
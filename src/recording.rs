@@ -0,0 +1,116 @@
+use std::collections::BTreeMap;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, FixedOffset, Local, SecondsFormat};
+use serde_json::Value;
+
+use crate::{block_stream, config, RENDERER};
+
+/// A block's data, as recorded with `--record`.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+struct Event {
+    time: String,
+    block: String,
+    data: Value,
+}
+
+/// Writes every block's data to a file as JSON lines.
+#[derive(Debug)]
+pub struct Recorder {
+    writer: std::io::LineWriter<std::fs::File>,
+}
+
+impl Recorder {
+    pub fn create(file: &Path) -> Result<Self> {
+        Ok(Self {
+            writer: std::io::LineWriter::new(std::fs::File::create(file)?),
+        })
+    }
+
+    pub fn record(&mut self, block: &str, data: &Value) -> Result<()> {
+        let event = Event {
+            time: Local::now().to_rfc3339_opts(SecondsFormat::Millis, false),
+            block: block.to_string(),
+            data: data.clone(),
+        };
+        writeln!(self.writer, "{}", serde_json::to_string(&event)?)?;
+
+        Ok(())
+    }
+}
+
+/// Run the render loop with data from `recording` in place of the configured blocks.
+///
+/// Events are replayed `speed` times faster than they were recorded.
+pub async fn replay(file: Option<PathBuf>, recording: PathBuf, speed: f64) -> Result<()> {
+    anyhow::ensure!(
+        speed.is_finite() && speed > 0.0,
+        "Replay speed must be a positive number"
+    );
+    let config = config::load_config(file).context("Failed to load config")?;
+    let events = read_events(&recording)
+        .with_context(|| format!("Failed to read recording {}", recording.display()))?;
+    let start_time = match events.first() {
+        Some((time, _)) => *time,
+        None => anyhow::bail!("Recording {} is empty", recording.display()),
+    };
+
+    RENDERER.configure(&config)?;
+    RENDERER
+        .set_root_template(&config)
+        .context("Failed to build template renderer")?;
+
+    let start = Instant::now();
+    let mut block_events: BTreeMap<String, Vec<(Instant, Value)>> = BTreeMap::new();
+    for (time, event) in events {
+        let offset = (time - start_time).to_std().unwrap_or(Duration::ZERO);
+        block_events
+            .entry(event.block)
+            .or_default()
+            .push((start + offset.div_f64(speed), event.data));
+    }
+    let mut block_streams = vec![];
+    for (name, events) in block_events {
+        let block = match config
+            .blocks
+            .iter()
+            .find(|(block_name, _)| *block_name == name)
+        {
+            Some((_, block)) => block.clone(),
+            None => {
+                eprintln!("Skipping recorded data for unknown block '{name}'");
+                continue;
+            }
+        };
+        let block_stream = block_stream::replay_stream(name.clone(), block, events)
+            .with_context(|| format!("Failed to initialize block '{name}'"))?;
+        block_streams.push(block_stream);
+    }
+    anyhow::ensure!(
+        !block_streams.is_empty(),
+        "Recording has no data for any configured block"
+    );
+
+    crate::run_blocks(config, block_streams).await
+}
+
+fn read_events(file: &Path) -> Result<Vec<(DateTime<FixedOffset>, Event)>> {
+    let reader = std::io::BufReader::new(std::fs::File::open(file)?);
+    let mut events = vec![];
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let event: Event = serde_json::from_str(&line)
+            .with_context(|| format!("Invalid event on line {}", index + 1))?;
+        let time = DateTime::parse_from_rfc3339(&event.time)
+            .with_context(|| format!("Invalid time on line {}", index + 1))?;
+        events.push((time, event));
+    }
+
+    Ok(events)
+}
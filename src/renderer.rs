@@ -1,26 +1,530 @@
+mod color;
+mod escape;
+mod format;
+mod gauge;
+mod thresholds;
+mod util;
+mod width;
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use once_cell::sync::Lazy;
+use serde_json::Value;
+
+use crate::config::{BlockOptions, Config, Escape, State, ThresholdsConfig};
+use crate::recording::Recorder;
 
 pub static RENDERER: Lazy<Renderer> = Lazy::new(Renderer::default);
 
-#[derive(Debug, Clone, Default)]
+/// Name of the root template. The empty string avoids conflicts with any block templates.
+pub const ROOT_TEMPLATE: &str = "";
+
+#[derive(Debug, Clone)]
 pub struct Renderer {
     tera: Arc<Mutex<tera::Tera>>,
+    autoescape: Arc<Mutex<Option<Escape>>>,
+    blocks: Arc<Mutex<HashMap<String, BlockState>>>,
+    globals: Arc<Mutex<Globals>>,
+    sources: Arc<Mutex<TemplateSources>>,
+    recorder: Arc<Mutex<Option<Recorder>>>,
+}
+
+/// Where each template came from, kept so they can be reloaded.
+#[derive(Debug, Clone, Default)]
+struct TemplateSources {
+    dir: Option<PathBuf>,
+    files: BTreeMap<String, PathBuf>,
+    inline: BTreeMap<String, String>,
+}
+
+/// Names of the values available to every template.
+const GLOBALS: [&str; 2] = ["vars", "palette"];
+
+/// Values available to every template.
+#[derive(Debug, Clone, Default)]
+struct Globals {
+    vars: Value,
+    palette: BTreeMap<String, String>,
+}
+
+/// Per-block state kept between renders.
+#[derive(Debug, Clone, Default)]
+struct BlockState {
+    last_data: Option<Value>,
+    history_len: usize,
+    history: VecDeque<Value>,
+    thresholds: Option<ThresholdsConfig>,
+    state: Option<State>,
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Self {
+            tera: Arc::new(Mutex::new(new_tera(None))),
+            autoescape: Arc::new(Mutex::new(None)),
+            blocks: Arc::new(Mutex::new(HashMap::new())),
+            globals: Arc::new(Mutex::new(Globals::default())),
+            sources: Arc::new(Mutex::new(TemplateSources::default())),
+            recorder: Arc::new(Mutex::new(None)),
+        }
+    }
+}
+
+/// A template set with yablocks' filters and functions, and no templates.
+fn new_tera(escape: Option<Escape>) -> tera::Tera {
+    let mut tera = tera::Tera::default();
+    tera.autoescape_on(vec![]);
+    if let Some(escape) = escape {
+        tera.set_escape_fn(escape.escape_fn());
+    }
+    for escape in Escape::ALL {
+        tera.register_filter(escape.filter_name(), escape::EscapeFilter(escape));
+    }
+    tera.register_filter("human_bytes", format::human_bytes);
+    tera.register_filter("human_rate", format::human_rate);
+    tera.register_filter("duration", format::duration);
+    tera.register_filter("pad", format::pad);
+    tera.register_filter("fixed_width", format::fixed_width);
+    tera.register_filter("si_prefix", format::si_prefix);
+    tera.register_filter("truncate_width", width::truncate_width);
+    tera.register_filter("pad_width", width::pad_width);
+    tera.register_filter("sparkline", gauge::sparkline);
+    tera.register_filter("average", gauge::average);
+    tera.register_function("ramp", gauge::ramp);
+    tera.register_function("bar", gauge::bar);
+    tera.register_function("threshold", gauge::threshold);
+    tera.register_function("gradient", color::gradient);
+    tera.register_function("lighten", color::lighten);
+    tera.register_function("darken", color::darken);
+    tera.register_function("mix", color::mix);
+    tera.register_function("alpha", color::alpha);
+    tera.register_function("color", color::color);
+
+    tera
 }
 
 impl Renderer {
     pub fn add_template(&self, name: &str, template: &str) -> Result<()> {
         self.tera.lock().unwrap().add_raw_template(name, template)?;
+        let mut sources = self.sources.lock().unwrap();
+        // An inline template replaces any template file loaded under the same name.
+        sources.files.remove(name);
+        sources
+            .inline
+            .insert(name.to_string(), template.to_string());
 
         Ok(())
     }
 
+    /// Add a template called `name` from the contents of `file`.
+    pub fn add_template_file(&self, name: &str, file: &Path) -> Result<()> {
+        let template = std::fs::read_to_string(file)
+            .with_context(|| format!("Failed to read template file {}", file.display()))?;
+        self.add_template(name, &template)?;
+        let mut sources = self.sources.lock().unwrap();
+        sources.inline.remove(name);
+        sources.files.insert(name.to_string(), file.to_path_buf());
+
+        Ok(())
+    }
+
+    /// Load every file in `dir` as a template named by its path relative to `dir`.
+    ///
+    /// These templates can be used with `{% import %}`, `{% include %}` and `{% extends %}`
+    /// from any other template, so the directory should be loaded before other templates.
+    pub fn load_templates_dir(&self, dir: &Path) -> Result<()> {
+        let templates = dir_templates(dir)?;
+        self.tera.lock().unwrap().add_template_files(templates)?;
+        self.sources.lock().unwrap().dir = Some(dir.to_path_buf());
+
+        Ok(())
+    }
+
+    /// Apply the global config: vars, palette, autoescaping and the templates directory.
+    pub fn configure(&self, config: &Config) -> Result<()> {
+        self.set_vars(&config.vars)?;
+        self.set_palette(crate::theme::build_palette(
+            config.theme_file.as_deref(),
+            &config.palette,
+        )?);
+        if let Some(escape) = config.autoescape {
+            self.set_autoescape(escape);
+        }
+        if let Some(dir) = &config.templates_dir {
+            self.load_templates_dir(dir)?;
+        }
+
+        Ok(())
+    }
+
+    /// Add the root template from `template` or `template-file`, or build one from the blocks
+    /// if neither is set.
+    pub fn set_root_template(&self, config: &Config) -> Result<()> {
+        match (&config.template, &config.template_file) {
+            (Some(template), None) => self.add_template(ROOT_TEMPLATE, template),
+            (None, Some(file)) => self.add_template_file(ROOT_TEMPLATE, file),
+            (Some(_), Some(_)) => Err(anyhow::anyhow!(
+                "Cannot use both template and template-file"
+            )),
+            (None, None) => {
+                let names: Vec<_> = config.blocks.iter().map(|(name, _)| name.clone()).collect();
+                self.add_template(
+                    ROOT_TEMPLATE,
+                    &default_root_template(&names, &config.separator)?,
+                )
+            }
+        }
+    }
+
+    /// Rebuild every template from its source, re-reading template files and the templates
+    /// directory.
+    ///
+    /// Files added to the templates directory are loaded, and removed ones are dropped. If any
+    /// template fails to load, the current templates are kept.
+    pub fn reload_templates(&self) -> Result<()> {
+        let sources = self.sources.lock().unwrap().clone();
+        let mut tera = new_tera(*self.autoescape.lock().unwrap());
+        if let Some(dir) = &sources.dir {
+            tera.add_template_files(dir_templates(dir)?)?;
+        }
+        let mut templates = vec![];
+        for (name, file) in &sources.files {
+            let template = std::fs::read_to_string(file)
+                .with_context(|| format!("Failed to read template file {}", file.display()))?;
+            templates.push((name.clone(), template));
+        }
+        templates.extend(sources.inline);
+        tera.add_raw_templates(templates)?;
+        *self.tera.lock().unwrap() = tera;
+
+        Ok(())
+    }
+
+    /// The template files and templates directory to watch for changes.
+    pub fn template_paths(&self) -> Vec<PathBuf> {
+        let sources = self.sources.lock().unwrap().clone();
+        sources
+            .dir
+            .into_iter()
+            .chain(sources.files.into_values())
+            .collect()
+    }
+
+    /// Escape every value interpolated into a block template using `escape`.
+    ///
+    /// Use the `safe` filter in a template to opt out for a specific value.
+    pub fn set_autoescape(&self, escape: Escape) {
+        self.tera.lock().unwrap().set_escape_fn(escape.escape_fn());
+        *self.autoescape.lock().unwrap() = Some(escape);
+    }
+
+    /// Make `vars` available to every template.
+    pub fn set_vars(&self, vars: &BTreeMap<String, toml::Value>) -> Result<()> {
+        self.globals.lock().unwrap().vars = serde_json::to_value(vars)?;
+
+        Ok(())
+    }
+
+    /// Make `palette` available to every template.
+    pub fn set_palette(&self, palette: BTreeMap<String, String>) {
+        self.globals.lock().unwrap().palette = palette;
+    }
+
+    /// Apply the options common to all blocks to the block called `name`.
+    pub fn set_block_options(&self, name: &str, options: &BlockOptions) {
+        let mut blocks = self.blocks.lock().unwrap();
+        let state = blocks.entry(name.to_string()).or_default();
+        state.history_len = options.history;
+        state.thresholds = options.thresholds.clone();
+    }
+
+    /// Forget a block's data, history and state, keeping its options.
+    pub fn reset_block(&self, name: &str) {
+        if let Some(state) = self.blocks.lock().unwrap().get_mut(name) {
+            state.last_data = None;
+            state.history.clear();
+            state.state = None;
+        }
+    }
+
+    /// Record the data from every block render to `recorder`.
+    pub fn set_recorder(&self, recorder: Recorder) {
+        *self.recorder.lock().unwrap() = Some(recorder);
+    }
+
     pub fn render(&self, name: &str, data: impl serde::Serialize) -> Result<String> {
-        let context = tera::Context::from_serialize(&data)?;
-        let rendered = self.tera.lock().unwrap().render(name, &context)?;
+        let data = serde_json::to_value(data)?;
+        if let Some(state) = self.blocks.lock().unwrap().get_mut(name) {
+            state.record(&data);
+        }
+        if name != ROOT_TEMPLATE {
+            if let Some(recorder) = self.recorder.lock().unwrap().as_mut() {
+                if let Err(error) = recorder.record(name, &data) {
+                    eprintln!("Failed to record data from {name}: {error:?}");
+                }
+            }
+        }
+
+        self.render_value(name, data)
+    }
+
+    /// Render a block again from its most recent data (e.g. after the palette changes).
+    ///
+    /// Returns `None` if the block hasn't produced any data yet.
+    pub fn rerender(&self, name: &str) -> Option<Result<String>> {
+        let data = self.last_data(name)?;
+
+        Some(self.render_value(name, data))
+    }
+
+    /// The most recent data a block was rendered with.
+    pub fn last_data(&self, name: &str) -> Option<Value> {
+        self.blocks.lock().unwrap().get(name)?.last_data.clone()
+    }
+
+    fn render_value(&self, name: &str, mut data: Value) -> Result<String> {
+        let globals = self.globals.lock().unwrap().clone();
+        if let Some(state) = self.blocks.lock().unwrap().get(name) {
+            state.decorate(&mut data, &globals.palette)?;
+        }
+        let mut context = match data {
+            Value::Object(map) => {
+                if let Some(key) = GLOBALS.iter().find(|key| map.contains_key(**key)) {
+                    anyhow::bail!("Field `{key}` conflicts with the global `{key}`");
+                }
+                tera::Context::from_value(Value::Object(map))?
+            }
+            _ => tera::Context::new(),
+        };
+        context.insert("vars", &globals.vars);
+        context.insert("palette", &globals.palette);
+
+        let mut tera = self.tera.lock().unwrap();
+        // Block output is already escaped by the time it reaches the root template.
+        if self.autoescape.lock().unwrap().is_some() && name != ROOT_TEMPLATE {
+            tera.autoescape_on(vec![""]);
+        } else {
+            tera.autoescape_on(vec![]);
+        }
+        let rendered = tera.render(name, &context)?;
 
         Ok(rendered)
     }
+
+    /// Render the root template from the latest output of each block.
+    ///
+    /// Each block's `state` (if it has thresholds) is also available as `states.<name>`.
+    pub fn render_root(&self, outputs: &BTreeMap<String, String>) -> Result<String> {
+        let mut data = serde_json::Map::new();
+        for (name, output) in outputs {
+            data.insert(name.clone(), Value::String(output.clone()));
+        }
+        data.insert("blocks".to_string(), serde_json::to_value(outputs)?);
+        let states: serde_json::Map<_, _> = self
+            .blocks
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|(name, state)| {
+                Some((name.clone(), serde_json::to_value(state.state?).ok()?))
+            })
+            .collect();
+        data.insert("states".to_string(), Value::Object(states));
+
+        self.render(ROOT_TEMPLATE, Value::Object(data))
+    }
+}
+
+/// Build a root template showing the non-empty outputs of `names` in order, joined by
+/// `separator`.
+fn default_root_template(names: &[String], separator: &str) -> Result<String> {
+    let names = names
+        .iter()
+        .map(|name| string_literal(name))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(format!(
+        "{{%- set_global parts = [] -%}}\
+         {{%- for name in [{}] -%}}\
+         {{%- if blocks[name] -%}}{{%- set_global parts = parts | concat(with=blocks[name]) -%}}{{%- endif -%}}\
+         {{%- endfor -%}}\
+         {{{{- parts | join(sep={}) -}}}}",
+        names.join(", "),
+        string_literal(separator)?
+    ))
+}
+
+/// Quote `s` as a tera string literal.
+fn string_literal(s: &str) -> Result<String> {
+    ['"', '\'', '`']
+        .into_iter()
+        .find(|quote| !s.contains(*quote))
+        .map(|quote| format!("{quote}{s}{quote}"))
+        .ok_or_else(|| anyhow::anyhow!("Cannot quote {s:?} in a template"))
+}
+
+/// Recursively list the files in `dir`.
+/// Every file in `dir`, paired with its path relative to `dir` as the template name.
+fn dir_templates(dir: &Path) -> Result<Vec<(PathBuf, Option<String>)>> {
+    let files = template_dir_files(dir)
+        .with_context(|| format!("Failed to read templates dir {}", dir.display()))?;
+    Ok(files
+        .into_iter()
+        .map(|file| {
+            let name = file
+                .strip_prefix(dir)
+                .unwrap_or(&file)
+                .to_string_lossy()
+                .into_owned();
+            (file, Some(name))
+        })
+        .collect())
+}
+
+fn template_dir_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            files.extend(template_dir_files(&path)?);
+        } else {
+            files.push(path);
+        }
+    }
+    files.sort();
+
+    Ok(files)
+}
+
+impl BlockState {
+    /// Record new data from the block.
+    fn record(&mut self, data: &Value) {
+        self.last_data = Some(data.clone());
+        if let Some(thresholds) = &self.thresholds {
+            self.state = Some(thresholds::classify(thresholds, data));
+        }
+        if self.history_len > 0 {
+            if self.history.len() == self.history_len {
+                self.history.pop_front();
+            }
+            self.history.push_back(data.clone());
+        }
+    }
+
+    /// Add the block's derived fields to `data`, failing if the data already has a field with
+    /// the same name.
+    ///
+    /// Threshold colours can be given as the name of a palette entry.
+    fn decorate(&self, data: &mut Value, palette: &BTreeMap<String, String>) -> Result<()> {
+        let map = match data {
+            Value::Object(map) => map,
+            _ => return Ok(()),
+        };
+        let mut insert = |key: &str, value: Value| {
+            anyhow::ensure!(
+                !map.contains_key(key),
+                "Field `{key}` conflicts with the block's own `{key}`"
+            );
+            map.insert(key.to_string(), value);
+            Ok(())
+        };
+        if let (Some(thresholds), Some(state)) = (&self.thresholds, self.state) {
+            insert("state", serde_json::json!(state))?;
+            if let Some(color) = thresholds.colors.get(&state) {
+                let color = palette.get(color).unwrap_or(color);
+                insert("color", Value::String(color.clone()))?;
+            }
+        }
+        if self.history_len > 0 {
+            insert("history", Value::from_iter(self.history.clone()))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::Renderer;
+    use crate::config::BlockOptions;
+
+    #[test]
+    fn reload_templates_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a"), "A").unwrap();
+        let renderer = Renderer::default();
+        renderer.load_templates_dir(dir.path()).unwrap();
+        renderer
+            .add_template(
+                "block",
+                "{% include 'a' ignore missing %}{% include 'b' ignore missing %}",
+            )
+            .unwrap();
+        assert_eq!(renderer.render("block", json!({})).unwrap(), "A");
+
+        // New files are loaded and deleted ones are dropped.
+        std::fs::remove_file(dir.path().join("a")).unwrap();
+        std::fs::write(dir.path().join("b"), "B").unwrap();
+        renderer.reload_templates().unwrap();
+        assert_eq!(renderer.render("block", json!({})).unwrap(), "B");
+
+        // A broken template keeps the current templates.
+        std::fs::write(dir.path().join("c"), "{% if %}").unwrap();
+        assert!(renderer.reload_templates().is_err());
+        assert_eq!(renderer.render("block", json!({})).unwrap(), "B");
+    }
+
+    #[test]
+    fn globals_conflict_with_block_fields() {
+        let renderer = Renderer::default();
+        renderer
+            .add_template("block", "{{ value }} {{ vars.name }}")
+            .unwrap();
+        let vars = [("name".to_string(), toml::Value::from("var"))].into();
+        renderer.set_vars(&vars).unwrap();
+
+        assert_eq!(
+            renderer.render("block", json!({"value": 1})).unwrap(),
+            "1 var"
+        );
+        for key in ["vars", "palette"] {
+            let error = renderer
+                .render("block", json!({"value": 1, key: 2}))
+                .unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                format!("Field `{key}` conflicts with the global `{key}`")
+            );
+        }
+    }
+
+    #[test]
+    fn derived_fields_conflict_with_block_fields() {
+        let renderer = Renderer::default();
+        renderer.add_template("block", "{{ value }}").unwrap();
+        let options: toml::Value = toml::from_str(
+            "history = 2\n[thresholds]\nfield = \"value\"\ncritical = 10\ncolors = { critical = \"red\" }",
+        )
+        .unwrap();
+        let options: BlockOptions = options.try_into().unwrap();
+        renderer.set_block_options("block", &options);
+
+        assert_eq!(
+            renderer.render("block", json!({"value": 20})).unwrap(),
+            "20"
+        );
+        for key in ["state", "color", "history"] {
+            let error = renderer
+                .render("block", json!({"value": 20, key: 1}))
+                .unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                format!("Field `{key}` conflicts with the block's own `{key}`")
+            );
+        }
+    }
 }
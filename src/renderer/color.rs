@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use super::util::{get_arg, get_required_arg};
+
+const DEFAULT_FORMAT: &str = "#RRGGBB";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Color {
+    a: f64,
+    r: f64,
+    g: f64,
+    b: f64,
+}
+
+impl Color {
+    const WHITE: Color = Color::opaque(255.0, 255.0, 255.0);
+    const BLACK: Color = Color::opaque(0.0, 0.0, 0.0);
+
+    const fn opaque(r: f64, g: f64, b: f64) -> Self {
+        Self { a: 255.0, r, g, b }
+    }
+
+    /// Parse `#RGB`, `#ARGB`, `#RRGGBB` or `#AARRGGBB` (the leading `#` is optional).
+    fn parse(s: &str) -> Option<Self> {
+        let hex = s.strip_prefix('#').unwrap_or(s);
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let digits: Vec<f64> = match hex.len() {
+            3 | 4 => hex
+                .chars()
+                .map(|c| c.to_digit(16).map(|d| (d * 17) as f64))
+                .collect::<Option<_>>()?,
+            6 | 8 => (0..hex.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok().map(f64::from))
+                .collect::<Option<_>>()?,
+            _ => return None,
+        };
+        match digits[..] {
+            [r, g, b] => Some(Self::opaque(r, g, b)),
+            [a, r, g, b] => Some(Self { a, r, g, b }),
+            _ => None,
+        }
+    }
+
+    fn with_alpha(self, a: f64) -> Self {
+        Self { a, ..self }
+    }
+
+    fn mix(&self, other: &Color, weight: f64) -> Self {
+        let weight = weight.clamp(0.0, 1.0);
+        let lerp = |a: f64, b: f64| a + (b - a) * weight;
+        Self {
+            a: lerp(self.a, other.a),
+            r: lerp(self.r, other.r),
+            g: lerp(self.g, other.g),
+            b: lerp(self.b, other.b),
+        }
+    }
+
+    /// Format using `#RRGGBB`, `#AARRGGBB` or `#RRGGBBAA`.
+    fn format(&self, format: &str) -> tera::Result<String> {
+        let [a, r, g, b] = [self.a, self.r, self.g, self.b].map(|c| c.round() as u8);
+        match format {
+            "#RRGGBB" => Ok(format!("#{r:02X}{g:02X}{b:02X}")),
+            "#AARRGGBB" => Ok(format!("#{a:02X}{r:02X}{g:02X}{b:02X}")),
+            "#RRGGBBAA" => Ok(format!("#{r:02X}{g:02X}{b:02X}{a:02X}")),
+            _ => Err(tera::Error::msg(format!(
+                "Invalid color format `{format}`: expected `#RRGGBB`, `#AARRGGBB` or `#RRGGBBAA`"
+            ))),
+        }
+    }
+}
+
+/// Interpolate between `colors` by where `value` falls between `min` (default 0) and `max`
+/// (default 100).
+pub fn gradient(args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let value: f64 = get_required_arg("gradient", "value", args)?;
+    let min: f64 = get_arg("gradient", "min", args, 0.0)?;
+    let max: f64 = get_arg("gradient", "max", args, 100.0)?;
+    let colors: Vec<String> = get_required_arg("gradient", "colors", args)?;
+    let colors = colors
+        .iter()
+        .map(|color| parse_color("gradient", color))
+        .collect::<tera::Result<Vec<_>>>()?;
+    if colors.is_empty() {
+        return Err(tera::Error::msg("`gradient` expected at least one color"));
+    }
+    if max <= min {
+        return Err(tera::Error::msg(
+            "`gradient` expected `max` to be greater than `min`",
+        ));
+    }
+
+    let position = ((value - min) / (max - min)).clamp(0.0, 1.0) * (colors.len() - 1) as f64;
+    let index = (position.floor() as usize).min(colors.len() - 1);
+    let color = match colors.get(index + 1) {
+        Some(next) => colors[index].mix(next, position - index as f64),
+        None => colors[index],
+    };
+
+    output("gradient", color, args, DEFAULT_FORMAT)
+}
+
+/// Mix `color` with white by `amount` (between 0 and 1).
+pub fn lighten(args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let color = get_color("lighten", "color", args)?;
+    let amount: f64 = get_required_arg("lighten", "amount", args)?;
+
+    output(
+        "lighten",
+        color.mix(&Color::WHITE.with_alpha(color.a), amount),
+        args,
+        DEFAULT_FORMAT,
+    )
+}
+
+/// Mix `color` with black by `amount` (between 0 and 1).
+pub fn darken(args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let color = get_color("darken", "color", args)?;
+    let amount: f64 = get_required_arg("darken", "amount", args)?;
+
+    output(
+        "darken",
+        color.mix(&Color::BLACK.with_alpha(color.a), amount),
+        args,
+        DEFAULT_FORMAT,
+    )
+}
+
+/// Mix colors `a` and `b`, with `weight` (default 0.5) being the proportion of `b`.
+pub fn mix(args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let a = get_color("mix", "a", args)?;
+    let b = get_color("mix", "b", args)?;
+    let weight: f64 = get_arg("mix", "weight", args, 0.5)?;
+
+    output("mix", a.mix(&b, weight), args, DEFAULT_FORMAT)
+}
+
+/// Set the opacity of `color` to `alpha` (between 0 and 1). Defaults to `#AARRGGBB` output.
+pub fn alpha(args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let color = get_color("alpha", "color", args)?;
+    let alpha: f64 = get_required_arg("alpha", "alpha", args)?;
+
+    output(
+        "alpha",
+        color.with_alpha(alpha.clamp(0.0, 1.0) * 255.0),
+        args,
+        "#AARRGGBB",
+    )
+}
+
+/// Convert `color` to another `format`.
+pub fn color(args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let color = get_color("color", "color", args)?;
+
+    output("color", color, args, DEFAULT_FORMAT)
+}
+
+fn get_color(caller: &str, name: &str, args: &HashMap<String, Value>) -> tera::Result<Color> {
+    let color: String = get_required_arg(caller, name, args)?;
+    parse_color(caller, &color)
+}
+
+fn parse_color(caller: &str, color: &str) -> tera::Result<Color> {
+    Color::parse(color)
+        .ok_or_else(|| tera::Error::msg(format!("`{caller}` received an invalid color `{color}`")))
+}
+
+fn output(
+    caller: &str,
+    color: Color,
+    args: &HashMap<String, Value>,
+    default_format: &str,
+) -> tera::Result<Value> {
+    let format: String = get_arg(caller, "format", args, default_format.to_string())?;
+    Ok(Value::String(color.format(&format)?))
+}
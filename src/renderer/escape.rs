@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use super::util::to_string;
+use crate::config::Escape;
+
+/// Tera filter wrapping one of the escape functions below.
+pub struct EscapeFilter(pub Escape);
+
+impl tera::Filter for EscapeFilter {
+    fn filter(&self, value: &Value, _args: &HashMap<String, Value>) -> tera::Result<Value> {
+        Ok(Value::String(self.0.escape_fn()(&to_string(value))))
+    }
+
+    fn is_safe(&self) -> bool {
+        true
+    }
+}
+
+impl Escape {
+    pub fn filter_name(&self) -> &'static str {
+        match self {
+            Escape::Lemonbar => "escape_lemonbar",
+            Escape::Dzen => "escape_dzen",
+            Escape::Xmobar => "escape_xmobar",
+            Escape::Pango => "escape_pango",
+            Escape::Json => "escape_json",
+        }
+    }
+
+    pub fn escape_fn(&self) -> fn(&str) -> String {
+        match self {
+            Escape::Lemonbar => escape_lemonbar,
+            Escape::Dzen => escape_dzen,
+            Escape::Xmobar => escape_xmobar,
+            Escape::Pango => escape_pango,
+            Escape::Json => escape_json,
+        }
+    }
+}
+
+/// Lemonbar treats `%{` as the start of a command, and `%%` as a literal `%`.
+fn escape_lemonbar(input: &str) -> String {
+    input.replace('%', "%%")
+}
+
+/// Dzen treats `^` as the start of a command, and `^^` as a literal `^`.
+fn escape_dzen(input: &str) -> String {
+    input.replace('^', "^^")
+}
+
+/// Xmobar has no escape for `<`, but a `<raw/>` tag can hold arbitrary text.
+fn escape_xmobar(input: &str) -> String {
+    input.replace('<', "<raw=1:</>")
+}
+
+fn escape_pango(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+    for c in input.chars() {
+        match c {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '"' => output.push_str("&quot;"),
+            '\'' => output.push_str("&#39;"),
+            c => output.push(c),
+        }
+    }
+    output
+}
+
+/// Escape for use inside a JSON string (the surrounding quotes are left to the template).
+fn escape_json(input: &str) -> String {
+    let quoted = serde_json::to_string(input).unwrap_or_default();
+    quoted[1..quoted.len() - 1].to_string()
+}
@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+use tera::try_get_value;
+
+use super::util::{get_arg, get_fill, get_required_arg, to_string};
+
+const SI_BYTE_UNITS: [&str; 7] = ["B", "kB", "MB", "GB", "TB", "PB", "EB"];
+const IEC_BYTE_UNITS: [&str; 7] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+const SI_PREFIXES: [(f64, &str); 11] = [
+    (1e18, "E"),
+    (1e15, "P"),
+    (1e12, "T"),
+    (1e9, "G"),
+    (1e6, "M"),
+    (1e3, "k"),
+    (1.0, ""),
+    (1e-3, "m"),
+    (1e-6, "µ"),
+    (1e-9, "n"),
+    (1e-12, "p"),
+];
+
+/// Format a number of bytes using SI (`kB`, `MB`, ...) or, with `iec=true`, IEC
+/// (`KiB`, `MiB`, ...) units.
+pub fn human_bytes(value: &Value, args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let bytes = try_get_value!("human_bytes", "value", f64, value);
+    let iec = get_arg("human_bytes", "iec", args, false)?;
+    let precision = get_arg("human_bytes", "precision", args, 1)?;
+
+    Ok(Value::String(format_bytes(bytes, iec, precision)))
+}
+
+/// Format a number of bytes per second, like `human_bytes` but with a `/s` suffix.
+pub fn human_rate(value: &Value, args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let bytes = try_get_value!("human_rate", "value", f64, value);
+    let iec = get_arg("human_rate", "iec", args, false)?;
+    let precision = get_arg("human_rate", "precision", args, 1)?;
+
+    Ok(Value::String(format!(
+        "{}/s",
+        format_bytes(bytes, iec, precision)
+    )))
+}
+
+/// Format a number of seconds as the two most significant units (e.g. `1h 02m`).
+pub fn duration(value: &Value, _args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let seconds = try_get_value!("duration", "value", f64, value);
+    let seconds = seconds.max(0.0).round() as u64;
+    let (days, hours, minutes, seconds) = (
+        seconds / 86400,
+        seconds % 86400 / 3600,
+        seconds % 3600 / 60,
+        seconds % 60,
+    );
+    let formatted = if days > 0 {
+        format!("{days}d {hours:02}h")
+    } else if hours > 0 {
+        format!("{hours}h {minutes:02}m")
+    } else if minutes > 0 {
+        format!("{minutes}m {seconds:02}s")
+    } else {
+        format!("{seconds}s")
+    };
+
+    Ok(Value::String(formatted))
+}
+
+/// Pad a value to at least `width` characters.
+///
+/// `align` can be `left` (the default), `right`, or `center`, and `fill` sets the padding
+/// character.
+pub fn pad(value: &Value, args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let s = to_string(value);
+    let width = get_required_arg("pad", "width", args)?;
+    let align: String = get_arg("pad", "align", args, "left".to_string())?;
+    let fill = get_fill("pad", args)?;
+
+    Ok(Value::String(pad_string(
+        &s,
+        s.chars().count(),
+        width,
+        &align,
+        fill,
+    )?))
+}
+
+/// Pad or truncate a value to exactly `width` characters. Takes the same arguments as `pad`.
+pub fn fixed_width(value: &Value, args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let s = to_string(value);
+    let width = get_required_arg("fixed_width", "width", args)?;
+    let align: String = get_arg("fixed_width", "align", args, "left".to_string())?;
+    let fill = get_fill("fixed_width", args)?;
+    let truncated: String = s.chars().take(width).collect();
+
+    let len = truncated.chars().count();
+
+    Ok(Value::String(pad_string(
+        &truncated, len, width, &align, fill,
+    )?))
+}
+
+/// Format a number with an SI prefix (e.g. `1.2 k`), with an optional `unit` suffix.
+pub fn si_prefix(value: &Value, args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let num = try_get_value!("si_prefix", "value", f64, value);
+    let precision = get_arg("si_prefix", "precision", args, 1)?;
+    let unit: String = get_arg("si_prefix", "unit", args, "".to_string())?;
+
+    let (scale, prefix) = SI_PREFIXES
+        .iter()
+        .find(|(scale, _)| num.abs() >= *scale)
+        .copied()
+        .unwrap_or((1.0, ""));
+    let num = if num == 0.0 { 0.0 } else { num / scale };
+
+    Ok(Value::String(format!("{num:.precision$} {prefix}{unit}")))
+}
+
+fn format_bytes(bytes: f64, iec: bool, precision: usize) -> String {
+    let (base, units) = if iec {
+        (1024.0, IEC_BYTE_UNITS)
+    } else {
+        (1000.0, SI_BYTE_UNITS)
+    };
+    let mut value = bytes;
+    let mut unit = 0;
+    while value.abs() >= base && unit < units.len() - 1 {
+        value /= base;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{value:.0} {}", units[unit])
+    } else {
+        format!("{value:.precision$} {}", units[unit])
+    }
+}
+
+/// Pad `s`, which is `len` columns wide, to `width` columns.
+pub fn pad_string(
+    s: &str,
+    len: usize,
+    width: usize,
+    align: &str,
+    fill: char,
+) -> tera::Result<String> {
+    let padding = width.saturating_sub(len);
+    let (left, right) = match align {
+        "left" => (0, padding),
+        "right" => (padding, 0),
+        "center" => (padding / 2, padding - padding / 2),
+        _ => {
+            return Err(tera::Error::msg(format!(
+                "Invalid align `{align}`: expected `left`, `right` or `center`"
+            )))
+        }
+    };
+    let fill = |n| fill.to_string().repeat(n);
+
+    Ok(format!("{}{s}{}", fill(left), fill(right)))
+}
@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+use tera::try_get_value;
+
+use super::util::{get_arg, get_required_arg};
+
+const PARTIAL_BLOCKS: [char; 8] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+const FULL_BLOCK: char = '█';
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Pick an icon from `icons` by where `value` falls between `min` (default 0) and `max`
+/// (default 100).
+pub fn ramp(args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let icons: Vec<Value> = get_required_arg("ramp", "icons", args)?;
+    if icons.is_empty() {
+        return Err(tera::Error::msg("`ramp` expected at least one icon"));
+    }
+    let fraction = get_fraction("ramp", args)?;
+    let index = ((fraction * icons.len() as f64) as usize).min(icons.len() - 1);
+
+    Ok(icons[index].clone())
+}
+
+/// Draw a horizontal bar `width` (default 10) characters wide using partial block glyphs.
+///
+/// Takes the same `value`, `min` and `max` arguments as `ramp`, and an optional `empty`
+/// character for the unfilled part of the bar.
+pub fn bar(args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let width: usize = get_arg("bar", "width", args, 10)?;
+    let empty: String = get_arg("bar", "empty", args, " ".to_string())?;
+    let fraction = get_fraction("bar", args)?;
+
+    let eighths = (fraction * (width * 8) as f64).round() as usize;
+    let full = eighths / 8;
+    let mut bar = FULL_BLOCK.to_string().repeat(full);
+    if full < width {
+        let partial = eighths % 8;
+        if partial > 0 {
+            bar.push(PARTIAL_BLOCKS[partial]);
+        } else {
+            bar.push_str(&empty);
+        }
+        bar.push_str(&empty.repeat(width - full - 1));
+    }
+
+    Ok(Value::String(bar))
+}
+
+/// Pick the output for the highest step that `value` has reached.
+///
+/// `steps` is either a map from thresholds to outputs, or an array of thresholds with a
+/// matching array of `values`. Returns `default` (or an empty string) if `value` is below
+/// every threshold.
+pub fn threshold(args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let value: f64 = get_required_arg("threshold", "value", args)?;
+    let default = args
+        .get("default")
+        .cloned()
+        .unwrap_or_else(|| Value::String("".to_string()));
+
+    let steps = match args.get("steps") {
+        Some(Value::Object(map)) => map
+            .iter()
+            .map(|(step, output)| match step.parse::<f64>() {
+                Ok(step) => Ok((step, output.clone())),
+                Err(_) => Err(tera::Error::msg(format!(
+                    "`threshold` expected numeric keys for `steps`, got `{step}`"
+                ))),
+            })
+            .collect::<tera::Result<Vec<_>>>()?,
+        Some(Value::Array(_)) => {
+            let steps: Vec<f64> = get_required_arg("threshold", "steps", args)?;
+            let values: Vec<Value> = get_required_arg("threshold", "values", args)?;
+            if steps.len() != values.len() {
+                return Err(tera::Error::msg(
+                    "`threshold` expected `steps` and `values` to be the same length",
+                ));
+            }
+            steps.into_iter().zip(values).collect()
+        }
+        _ => {
+            return Err(tera::Error::msg(
+                "`threshold` expected `steps` to be a map or an array",
+            ))
+        }
+    };
+
+    Ok(steps
+        .into_iter()
+        .filter(|(step, _)| value >= *step)
+        .max_by(|(a, _), (b, _)| a.total_cmp(b))
+        .map(|(_, output)| output)
+        .unwrap_or(default))
+}
+
+/// Get the position of `value` between `min` and `max` as a number between 0 and 1.
+fn get_fraction(caller: &str, args: &HashMap<String, Value>) -> tera::Result<f64> {
+    let value: f64 = get_required_arg(caller, "value", args)?;
+    let min: f64 = get_arg(caller, "min", args, 0.0)?;
+    let max: f64 = get_arg(caller, "max", args, 100.0)?;
+    if max <= min {
+        return Err(tera::Error::msg(format!(
+            "`{caller}` expected `max` to be greater than `min`"
+        )));
+    }
+
+    Ok(((value - min) / (max - min)).clamp(0.0, 1.0))
+}
+
+/// Render an array of numbers as a line of block characters.
+///
+/// The line is scaled between the smallest and largest values unless `min` or `max` are given.
+pub fn sparkline(value: &Value, args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let values = try_get_value!("sparkline", "value", Vec<f64>, value);
+    let data_min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let data_max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let min: f64 = get_arg("sparkline", "min", args, data_min)?;
+    let max: f64 = get_arg("sparkline", "max", args, data_max)?;
+
+    let line = values
+        .iter()
+        .map(|value| {
+            let fraction = if max > min {
+                ((value - min) / (max - min)).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            let index = (fraction * (SPARKS.len() - 1) as f64).round() as usize;
+            SPARKS[index]
+        })
+        .collect();
+
+    Ok(Value::String(line))
+}
+
+/// Average an array of numbers (e.g. a block's `history`).
+pub fn average(value: &Value, _args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let values = try_get_value!("average", "value", Vec<f64>, value);
+    if values.is_empty() {
+        return Ok(Value::Null);
+    }
+
+    Ok(Value::from(
+        values.iter().sum::<f64>() / values.len() as f64,
+    ))
+}
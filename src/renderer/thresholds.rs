@@ -0,0 +1,43 @@
+use serde_json::Value;
+
+use crate::config::{State, ThresholdsConfig};
+
+/// Classify the configured field of `data`.
+///
+/// Returns the most severe state whose threshold has been reached. If the thresholds decrease
+/// with severity (e.g. for battery charge) then lower values are treated as more severe.
+pub fn classify(thresholds: &ThresholdsConfig, data: &Value) -> State {
+    let value = match tera::dotted_pointer(data, &thresholds.field) {
+        Some(Value::Number(n)) => n.as_f64(),
+        Some(Value::String(s)) => s.trim().parse().ok(),
+        _ => None,
+    };
+    let value = match value {
+        Some(value) => value,
+        None => return State::Idle,
+    };
+
+    let steps: Vec<(State, f64)> = [
+        (State::Info, thresholds.info),
+        (State::Good, thresholds.good),
+        (State::Warning, thresholds.warning),
+        (State::Critical, thresholds.critical),
+    ]
+    .into_iter()
+    .filter_map(|(state, threshold)| Some((state, threshold?)))
+    .collect();
+    let descending = steps.windows(2).any(|pair| pair[1].1 < pair[0].1);
+
+    steps
+        .into_iter()
+        .rev()
+        .find(|(_, threshold)| {
+            if descending {
+                value <= *threshold
+            } else {
+                value >= *threshold
+            }
+        })
+        .map(|(state, _)| state)
+        .unwrap_or(State::Idle)
+}
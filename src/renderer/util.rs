@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+/// Convert a value to a string without quoting strings.
+pub fn to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
+
+/// Get an optional argument to a filter or function, falling back to `default`.
+pub fn get_arg<T: DeserializeOwned>(
+    caller: &str,
+    name: &str,
+    args: &HashMap<String, Value>,
+    default: T,
+) -> tera::Result<T> {
+    match args.get(name) {
+        Some(value) => from_arg(caller, name, value),
+        None => Ok(default),
+    }
+}
+
+/// Get a required argument to a filter or function.
+pub fn get_required_arg<T: DeserializeOwned>(
+    caller: &str,
+    name: &str,
+    args: &HashMap<String, Value>,
+) -> tera::Result<T> {
+    match args.get(name) {
+        Some(value) => from_arg(caller, name, value),
+        None => Err(tera::Error::msg(format!(
+            "`{caller}` expected an arg called `{name}`"
+        ))),
+    }
+}
+
+/// Get the single character `fill` argument used by the padding filters.
+pub fn get_fill(caller: &str, args: &HashMap<String, Value>) -> tera::Result<char> {
+    let fill: String = get_arg(caller, "fill", args, " ".to_string())?;
+    let mut chars = fill.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        _ => Err(tera::Error::msg(format!(
+            "`{caller}` expected a single character for arg `fill`, got `{fill}`"
+        ))),
+    }
+}
+
+fn from_arg<T: DeserializeOwned>(caller: &str, name: &str, value: &Value) -> tera::Result<T> {
+    tera::from_value(value.clone()).map_err(|_| {
+        tera::Error::msg(format!(
+            "`{caller}` received an incorrect type for arg `{name}`: got `{value}`"
+        ))
+    })
+}
@@ -0,0 +1,50 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use super::format::pad_string;
+use super::util::{get_arg, get_fill, get_required_arg, to_string};
+
+/// Truncate a value to at most `width` display columns, ending with `end` (default `…`) if
+/// truncated.
+pub fn truncate_width(value: &Value, args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let s = to_string(value);
+    let width: usize = get_required_arg("truncate_width", "width", args)?;
+    let end: String = get_arg("truncate_width", "end", args, "…".to_string())?;
+    if s.width() <= width {
+        return Ok(Value::String(s));
+    }
+
+    let mut truncated = take_width(&s, width.saturating_sub(end.width()));
+    truncated.push_str(&end);
+
+    Ok(Value::String(truncated))
+}
+
+/// Pad a value to at least `width` display columns. Takes the same arguments as `pad`.
+pub fn pad_width(value: &Value, args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let s = to_string(value);
+    let width = get_required_arg("pad_width", "width", args)?;
+    let align: String = get_arg("pad_width", "align", args, "left".to_string())?;
+    let fill = get_fill("pad_width", args)?;
+
+    Ok(Value::String(pad_string(
+        &s,
+        s.width(),
+        width,
+        &align,
+        fill,
+    )?))
+}
+
+/// Take as many characters from the start of `s` as fit in `width` display columns.
+fn take_width(s: &str, width: usize) -> String {
+    let mut taken = 0;
+    s.chars()
+        .take_while(|c| {
+            taken += c.width().unwrap_or(0);
+            taken <= width
+        })
+        .collect()
+}
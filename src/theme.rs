@@ -0,0 +1,149 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+/// Build the template palette from an optional theme file and the configured palette.
+///
+/// Entries in the configured palette take precedence over colours from the theme file.
+pub fn build_palette(
+    theme_file: Option<&Path>,
+    palette: &BTreeMap<String, String>,
+) -> Result<BTreeMap<String, String>> {
+    let mut colors = match theme_file {
+        Some(file) => load_theme(file)
+            .with_context(|| format!("Failed to load theme file {}", file.display()))?,
+        None => BTreeMap::new(),
+    };
+    colors.extend(palette.clone());
+
+    Ok(colors)
+}
+
+/// Load colours from a pywal `colors.json`, a base16 YAML scheme, or an Xresources file.
+///
+/// The format is chosen by file extension, with anything other than `.json`, `.yaml` or `.yml`
+/// treated as Xresources.
+fn load_theme(file: &Path) -> Result<BTreeMap<String, String>> {
+    let contents = std::fs::read_to_string(file)?;
+    match file.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => parse_pywal(&contents),
+        Some("yaml" | "yml") => Ok(parse_base16(&contents)),
+        _ => Ok(parse_xresources(&contents)),
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct PywalTheme {
+    #[serde(default)]
+    special: BTreeMap<String, String>,
+    #[serde(default)]
+    colors: BTreeMap<String, String>,
+}
+
+/// pywal provides `background`, `foreground` and `cursor`, and `color0` to `color15`.
+fn parse_pywal(contents: &str) -> Result<BTreeMap<String, String>> {
+    let theme: PywalTheme = serde_json::from_str(contents)?;
+    let mut colors = theme.special;
+    colors.extend(theme.colors);
+
+    Ok(colors)
+}
+
+/// base16 schemes provide `base00` to `base0F`, either at the top level or under `palette`.
+fn parse_base16(contents: &str) -> BTreeMap<String, String> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.trim().split_once(':')?;
+            let value = value.trim().split(" #").next().unwrap_or_default();
+            let value = value.trim().trim_matches(|c| c == '"' || c == '\'');
+            let value = match value.strip_prefix('#') {
+                Some(_) => value.to_string(),
+                None => format!("#{value}"),
+            };
+            let is_hex = value.len() == 7 && value[1..].chars().all(|c| c.is_ascii_hexdigit());
+            (key.starts_with("base") && is_hex).then(|| (key.to_string(), value))
+        })
+        .collect()
+}
+
+/// Xresources entries like `*.color1: #FF0000` or `URxvt*background: #000000` are keyed by
+/// the last component of the resource name (`color1`, `background`).
+///
+/// Values can name a `#define` macro, as in `#define base00 #1d1f21` and `*color0: base00`.
+fn parse_xresources(contents: &str) -> BTreeMap<String, String> {
+    let mut defines = BTreeMap::new();
+    let mut entries = vec![];
+    for line in contents.lines().map(str::trim) {
+        if let Some(define) = line.strip_prefix("#define") {
+            let mut parts = define.split_whitespace();
+            if let (Some(name), Some(value)) = (parts.next(), parts.next()) {
+                defines.insert(name.to_string(), value.to_string());
+            }
+            continue;
+        }
+        if line.starts_with('!') || line.starts_with('#') {
+            continue;
+        }
+        let Some((resource, value)) = line.split_once(':') else {
+            continue;
+        };
+        let key = resource
+            .rsplit(['.', '*'])
+            .next()
+            .unwrap_or_default()
+            .trim();
+        if !key.is_empty() {
+            entries.push((key.to_string(), value.trim().to_string()));
+        }
+    }
+
+    entries
+        .into_iter()
+        .map(|(key, mut value)| {
+            // Follow chains of macros, giving up on cycles.
+            for _ in 0..defines.len() {
+                match defines.get(&value) {
+                    Some(defined) => value = defined.clone(),
+                    None => break,
+                }
+            }
+            (key, value)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_xresources;
+
+    #[test]
+    fn xresources_defines() {
+        let contents = "\
+! Base16 Tomorrow Night
+#define base00 #1d1f21
+#define base08 #cc6666
+#define red base08
+
+*foreground: #c5c8c6
+*.color0: base00
+URxvt*color1: red
+*color2: undefined
+";
+        let colors = parse_xresources(contents);
+        let colors: Vec<_> = colors
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect();
+        assert_eq!(
+            colors,
+            [
+                ("color0", "#1d1f21"),
+                ("color1", "#cc6666"),
+                ("color2", "undefined"),
+                ("foreground", "#c5c8c6"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,81 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use futures::channel::mpsc::Receiver;
+use futures::{FutureExt, SinkExt, StreamExt};
+use notify::Watcher;
+
+static DEBOUNCE_TIME: std::time::Duration = std::time::Duration::from_millis(10);
+
+/// Watches a set of files and directories for changes.
+///
+/// Like the inotify block, this watches the directory containing each file so that files
+/// which are replaced rather than modified are still picked up. Directories are watched
+/// recursively, and any change inside them counts.
+pub struct FileWatcher {
+    files: Vec<PathBuf>,
+    dirs: Vec<PathBuf>,
+    rx: Receiver<notify::Result<notify::Event>>,
+    _watcher: notify::RecommendedWatcher,
+}
+
+impl FileWatcher {
+    pub fn new(files: Vec<PathBuf>) -> Result<Self> {
+        let cwd = std::env::current_dir()?;
+        let (dirs, files): (Vec<PathBuf>, Vec<PathBuf>) = files
+            .into_iter()
+            .map(|file| cwd.join(file))
+            .partition(|path| path.is_dir());
+        let (mut tx, rx) = futures::channel::mpsc::channel(1);
+        let mut watcher = notify::RecommendedWatcher::new(
+            move |res| {
+                futures::executor::block_on(async {
+                    let _ = tx.send(res).await;
+                })
+            },
+            notify::Config::default(),
+        )?;
+        for file in &files {
+            let watch_dir = file.parent().unwrap_or_else(|| std::path::Path::new("/"));
+            watcher.watch(watch_dir, notify::RecursiveMode::NonRecursive)?;
+        }
+        for dir in &dirs {
+            watcher.watch(dir, notify::RecursiveMode::Recursive)?;
+        }
+        Ok(Self {
+            files,
+            dirs,
+            rx,
+            _watcher: watcher,
+        })
+    }
+
+    /// Wait until one of the watched files changes. Never returns if nothing is watched.
+    pub async fn changed(&mut self) {
+        if self.files.is_empty() && self.dirs.is_empty() {
+            return futures::future::pending().await;
+        }
+        loop {
+            let mut results = match self.rx.next().await {
+                Some(result) => vec![result],
+                None => return futures::future::pending().await,
+            };
+            tokio::time::sleep(DEBOUNCE_TIME).await;
+            while let Some(result) = self.rx.next().now_or_never().flatten() {
+                results.push(result);
+            }
+            let changed = results.into_iter().any(|result| match result {
+                Ok(event) => event.paths.iter().any(|path| {
+                    self.files.contains(path) || self.dirs.iter().any(|dir| path.starts_with(dir))
+                }),
+                Err(error) => {
+                    eprintln!("Error watching files: {error:?}");
+                    false
+                }
+            });
+            if changed {
+                return;
+            }
+        }
+    }
+}
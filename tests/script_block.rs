@@ -0,0 +1,52 @@
+//! Run script blocks through the yablocks binary.
+
+use std::process::Command;
+
+/// Print the output of `config` once, and return stdout and stderr.
+fn run_once(config: &str) -> (String, String) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    std::fs::write(&path, config).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_yablocks"))
+        .arg("--configfile")
+        .arg(path)
+        .args(["--once", "--timeout", "5"])
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(output.status.success(), "{stderr}");
+    (String::from_utf8(output.stdout).unwrap(), stderr)
+}
+
+#[test]
+fn print_goes_to_stderr() {
+    let (stdout, stderr) = run_once(
+        r#"
+        [blocks.script]
+        kind = "script"
+        script = '''
+        print("printed");
+        debug("debugged");
+        "output"
+        '''
+        "#,
+    );
+    assert_eq!(stdout, "output\n");
+    assert!(stderr.contains("printed"), "{stderr}");
+    assert!(stderr.contains("debugged"), "{stderr}");
+}
+
+#[test]
+fn run_reports_signals_as_failure() {
+    let (stdout, _) = run_once(
+        r#"
+        [blocks.script]
+        kind = "script"
+        script = '''
+        let result = run("sh", ["-c", "kill -9 $$"]);
+        result.status
+        '''
+        "#,
+    );
+    assert_eq!(stdout, "-1\n");
+}
@@ -0,0 +1,217 @@
+//! Run the blocks which read from procfs and sysfs against fake directory trees.
+
+use std::io::{BufRead, BufReader, Lines};
+use std::path::Path;
+use std::process::{Child, ChildStdout, Command, Stdio};
+
+/// Write `contents` to `path` under `root`, creating any missing directories.
+fn write(root: &Path, path: &str, contents: &str) {
+    let path = root.join(path);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, contents).unwrap();
+}
+
+fn yablocks(root: &Path, config: &str) -> Command {
+    write(root, "config.toml", config);
+    let mut command = Command::new(env!("CARGO_BIN_EXE_yablocks"));
+    command.arg("--configfile").arg(root.join("config.toml"));
+    command
+}
+
+/// Print the output once, and return it.
+fn run_once(root: &Path, config: &str) -> String {
+    let output = yablocks(root, config)
+        .args(["--once", "--timeout", "5"])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .trim_end()
+        .to_string()
+}
+
+/// A running yablocks process, killed when dropped.
+struct Running {
+    child: Child,
+    lines: Lines<BufReader<ChildStdout>>,
+}
+
+impl Running {
+    fn spawn(root: &Path, config: &str) -> Self {
+        let mut child = yablocks(root, config)
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let lines = BufReader::new(child.stdout.take().unwrap()).lines();
+        Self { child, lines }
+    }
+
+    fn next_line(&mut self) -> String {
+        self.lines.next().unwrap().unwrap()
+    }
+}
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn proc_stat(user: u64, system: u64, idle: u64) -> String {
+    format!(
+        "cpu  {user} 0 {system} {idle} 0 0 0 0 0 0\n\
+         cpu0 {user} 0 {system} {idle} 0 0 0 0 0 0\n\
+         ctxt 1000\n\
+         btime 1700000000\n\
+         processes 100\n"
+    )
+}
+
+#[test]
+fn cpu() {
+    let root = tempfile::tempdir().unwrap();
+    let root = root.path();
+    write(root, "proc/stat", &proc_stat(100, 100, 800));
+    let config = format!(
+        r#"
+        header = "ready"
+        proc-root = "{}/proc"
+
+        [blocks.cpu]
+        kind = "cpu"
+        interval = 1
+        template = "{{{{ cpu_times.non_idle }}}} {{{{ cpu_times.user }}}} {{{{ cpu_times.idle }}}}"
+        "#,
+        root.display()
+    );
+
+    // The header is printed once the block has taken its first reading.
+    let mut yablocks = Running::spawn(root, &config);
+    assert_eq!(yablocks.next_line(), "ready");
+    write(root, "proc/stat", &proc_stat(150, 150, 900));
+    assert_eq!(yablocks.next_line(), "50 25 50");
+}
+
+#[test]
+fn cpu_block_proc_root_override() {
+    let root = tempfile::tempdir().unwrap();
+    let root = root.path();
+    write(root, "fake/stat", &proc_stat(100, 100, 800));
+    let config = format!(
+        r#"
+        header = "ready"
+        proc-root = "/nonexistent"
+
+        [blocks.cpu]
+        kind = "cpu"
+        interval = 1
+        proc-root = "{}/fake"
+        template = "{{{{ cpu_times.system }}}}"
+        "#,
+        root.display()
+    );
+
+    let mut yablocks = Running::spawn(root, &config);
+    assert_eq!(yablocks.next_line(), "ready");
+    write(root, "fake/stat", &proc_stat(100, 300, 1000));
+    assert_eq!(yablocks.next_line(), "50");
+}
+
+#[test]
+fn network() {
+    let root = tempfile::tempdir().unwrap();
+    let root = root.path();
+    write(root, "sys/class/net/fake0/ifindex", "999999\n");
+    write(root, "sys/class/net/fake0/operstate", "up\n");
+    write(
+        root,
+        "proc/net/route",
+        "Iface\tDestination\tGateway\tFlags\tRefCnt\tUse\tMetric\tMask\tMTU\tWindow\tIRTT\n\
+         fake0\t0002A8C0\t00000000\t0001\t0\t0\t0\t00FFFFFF\t0\t0\t0\n\
+         fake0\t00000000\t0102A8C0\t0003\t0\t0\t0\t00000000\t0\t0\t0\n",
+    );
+    let config = format!(
+        r#"
+        proc-root = "{root}/proc"
+        sys-root = "{root}/sys"
+
+        [blocks.net]
+        kind = "network"
+        device = "fake0"
+        template = "{{{{ device }}}} {{{{ operstate }}}} {{{{ ipv4_gateway }}}} {{{{ ipv6_gateway | default(value='none') }}}}"
+        "#,
+        root = root.display()
+    );
+
+    assert_eq!(run_once(root, &config), "fake0 up 192.168.2.1 none");
+}
+
+#[test]
+fn network_stats() {
+    let root = tempfile::tempdir().unwrap();
+    let root = root.path();
+    let write_stats = |rx_bytes: u64, tx_bytes: u64| {
+        let stats = root.join("sys/class/net/fake0/statistics");
+        write(&stats, "rx_bytes", &format!("{rx_bytes}\n"));
+        write(&stats, "tx_bytes", &format!("{tx_bytes}\n"));
+        write(&stats, "rx_packets", "10\n");
+        write(&stats, "tx_packets", "5\n");
+    };
+    write_stats(1000, 500);
+    let config = format!(
+        r#"
+        sys-root = "{}/sys"
+
+        [blocks.net]
+        kind = "network-stats"
+        device = "fake0"
+        interval = 1
+        template = "{{{{ rx_bytes_per_sec }}}} {{{{ tx_bytes_per_sec }}}}"
+        "#,
+        root.display()
+    );
+
+    let mut yablocks = Running::spawn(root, &config);
+    assert_eq!(yablocks.next_line(), "0 0");
+    write_stats(3000, 1500);
+    assert_eq!(yablocks.next_line(), "2000 1000");
+}
+
+#[test]
+fn temperature() {
+    let root = tempfile::tempdir().unwrap();
+    let root = root.path();
+    write(root, "sys/class/hwmon/hwmon0/name", "acpitz\n");
+    write(root, "sys/class/hwmon/hwmon0/temp1_input", "27800\n");
+    write(root, "sys/class/hwmon/hwmon1/name", "coretemp\n");
+    write(root, "sys/class/hwmon/hwmon1/temp1_input", "45000\n");
+    write(root, "sys/class/hwmon/hwmon1/temp1_label", "Package id 0\n");
+    write(root, "sys/class/hwmon/hwmon1/temp2_input", "50500\n");
+    write(root, "sys/class/hwmon/hwmon2/name", "nvme\n");
+    write(root, "sys/class/hwmon/hwmon2/fan1_input", "1200\n");
+    let config = format!(
+        r#"
+        [blocks.temp]
+        kind = "temperature"
+        interval = 1
+        sys-root = "{}/sys"
+        template = """
+        {{%- for chip, sensors in chips -%}}
+        {{{{ chip }}}}:{{% for sensor in sensors %}} {{{{ sensor.label }}}}={{{{ sensor.value }}}}{{% endfor %}};
+        {{%- endfor -%}}
+        """
+        "#,
+        root.display()
+    );
+
+    assert_eq!(
+        run_once(root, &config),
+        "acpitz-hwmon0: temp1=27.8;coretemp-hwmon1: Package id 0=45 temp2=50.5;"
+    );
+}